@@ -23,17 +23,18 @@ fn main() {
 
   println!("cargo:rustc-link-search=native={}", lib_destination.display());
 
-  for lib in lib_names() {
-    println!("cargo:rustc-link-lib=static={}", lib);
-  }
-
-  // Avoid building/linking the inlining lib if only detour/detour_crowd are
-  // used (since they have no "inline" definitions).
-  if cfg!(any(feature = "recast", feature = "detour_tile_cache")) {
+  // Avoid building/linking the inlining lib if no libraries are enabled. The
+  // inlining lib references the recastnavigation libs, so it must be linked
+  // before them.
+  if cfg!(any(feature = "recast", feature = "detour")) {
     build_and_link_inline_lib(&include_dirs, &defines);
     generate_inline_bindings(&include_dirs, &defines);
   }
 
+  for lib in lib_names() {
+    println!("cargo:rustc-link-lib=static={}", lib);
+  }
+
   generate_recast_bindings(&include_dirs, &defines);
 }
 
@@ -69,6 +70,10 @@ fn is_nightly() -> bool {
 
 fn lib_names() -> Vec<String> {
   let mut root_names = Vec::new();
+  // DebugUtils depends on the other libraries, so it must come first when
+  // linking statically.
+  #[cfg(feature = "debug_utils")]
+  root_names.push("DebugUtils");
   #[cfg(feature = "recast")]
   root_names.push("Recast");
   #[cfg(feature = "detour")]
@@ -168,6 +173,7 @@ fn build_recast() -> (PathBuf, Vec<PathBuf>, HashMap<String, Option<String>>) {
       "recastnavigation/Detour/Include".into(),
       "recastnavigation/DetourCrowd/Include".into(),
       "recastnavigation/DetourTileCache/Include".into(),
+      "recastnavigation/DebugUtils/Include".into(),
     ],
     defines,
   )
@@ -286,6 +292,28 @@ fn generate_recast_bindings(
           find_in_include_dirs(include_dirs, "DetourCrowd.h")
             .expect("DetourCrowd.h is present in the include dirs."),
         )
+        .header(
+          find_in_include_dirs(include_dirs, "DetourPathCorridor.h")
+            .expect("DetourPathCorridor.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "DetourLocalBoundary.h")
+            .expect("DetourLocalBoundary.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "DetourProximityGrid.h")
+            .expect("DetourProximityGrid.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "DetourPathQueue.h")
+            .expect("DetourPathQueue.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "DetourObstacleAvoidance.h")
+            .expect(
+              "DetourObstacleAvoidance.h is present in the include dirs.",
+            ),
+        )
         .blocklist_file(".*DetourAlloc\\.h")
         .blocklist_file(".*DetourNavMesh\\.h")
         .blocklist_file(".*DetourNavMeshQuery\\.h")
@@ -315,6 +343,43 @@ fn generate_recast_bindings(
     },
     out_path.join("detour_tile_cache.rs"),
   );
+
+  #[cfg(feature = "debug_utils")]
+  create_bindings(
+    include_dirs,
+    defines,
+    |builder| {
+      builder
+        .header(
+          find_in_include_dirs(include_dirs, "DebugDraw.h")
+            .expect("DebugDraw.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "RecastDebugDraw.h")
+            .expect("RecastDebugDraw.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "DetourDebugDraw.h")
+            .expect("DetourDebugDraw.h is present in the include dirs."),
+        )
+        .header(
+          find_in_include_dirs(include_dirs, "RecastDump.h")
+            .expect("RecastDump.h is present in the include dirs."),
+        )
+        .blocklist_file(".*Recast\\.h")
+        .blocklist_file(".*RecastAlloc\\.h")
+        .blocklist_file(".*DetourAlloc\\.h")
+        .blocklist_file(".*DetourNavMesh\\.h")
+        .blocklist_file(".*DetourNavMeshQuery\\.h")
+        .blocklist_file(".*DetourStatus\\.h")
+        .blocklist_file(".*DetourTileCacheBuilder\\.h")
+        // The DebugUtils headers forward declare the Recast and Detour types
+        // they use, which would otherwise be bound again as opaque types.
+        .blocklist_type("rc.*")
+        .blocklist_type("dt.*")
+    },
+    out_path.join("debug_utils.rs"),
+  );
 }
 
 fn build_and_link_inline_lib(
@@ -338,6 +403,9 @@ fn build_and_link_inline_lib(
   if cfg!(feature = "detour_tile_cache") {
     build.define("DETOUR_TILE_CACHE", None);
   }
+  if cfg!(feature = "debug_utils") {
+    build.define("DEBUG_UTILS", None);
+  }
 
   for (name, value) in defines.iter() {
     build.define(
@@ -387,6 +455,9 @@ fn generate_inline_bindings(
   if cfg!(feature = "detour_tile_cache") {
     builder = builder.clang_args(["-DDETOUR_TILE_CACHE"].iter());
   }
+  if cfg!(feature = "debug_utils") {
+    builder = builder.clang_args(["-DDEBUG_UTILS"].iter());
+  }
 
   #[cfg(feature = "detour_large_nav_meshes")]
   let builder = builder.clang_args(["-DDT_POLYREF64"]);
@@ -8,6 +8,223 @@ rcContext* CreateContext(bool state) { return new rcContext(state); }
 
 void DeleteContext(rcContext* context) { delete context; }
 
+class ForwardVtableContext : public rcContext {
+ public:
+  ForwardVtableContext(
+      void* object_ptr, bool state,
+      ForwardVtableContextResetLogFn reset_log_fn,
+      ForwardVtableContextLogFn log_fn,
+      ForwardVtableContextResetTimersFn reset_timers_fn,
+      ForwardVtableContextStartTimerFn start_timer_fn,
+      ForwardVtableContextStopTimerFn stop_timer_fn,
+      ForwardVtableContextGetAccumulatedTimeFn get_accumulated_time_fn)
+      : rcContext(state),
+        object_ptr_(object_ptr),
+        reset_log_fn_(reset_log_fn),
+        log_fn_(log_fn),
+        reset_timers_fn_(reset_timers_fn),
+        start_timer_fn_(start_timer_fn),
+        stop_timer_fn_(stop_timer_fn),
+        get_accumulated_time_fn_(get_accumulated_time_fn) {}
+
+ protected:
+  void doResetLog() override { reset_log_fn_(object_ptr_); }
+
+  void doLog(const rcLogCategory category, const char* msg,
+             const int len) override {
+    log_fn_(object_ptr_, category, msg, len);
+  }
+
+  void doResetTimers() override { reset_timers_fn_(object_ptr_); }
+
+  void doStartTimer(const rcTimerLabel label) override {
+    start_timer_fn_(object_ptr_, label);
+  }
+
+  void doStopTimer(const rcTimerLabel label) override {
+    stop_timer_fn_(object_ptr_, label);
+  }
+
+  int doGetAccumulatedTime(const rcTimerLabel label) const override {
+    return get_accumulated_time_fn_(object_ptr_, label);
+  }
+
+ private:
+  void* object_ptr_;
+  ForwardVtableContextResetLogFn reset_log_fn_;
+  ForwardVtableContextLogFn log_fn_;
+  ForwardVtableContextResetTimersFn reset_timers_fn_;
+  ForwardVtableContextStartTimerFn start_timer_fn_;
+  ForwardVtableContextStopTimerFn stop_timer_fn_;
+  ForwardVtableContextGetAccumulatedTimeFn get_accumulated_time_fn_;
+};
+
+rcContext* CreateForwardedContext(
+    void* object_ptr, bool state, ForwardVtableContextResetLogFn reset_log_fn,
+    ForwardVtableContextLogFn log_fn,
+    ForwardVtableContextResetTimersFn reset_timers_fn,
+    ForwardVtableContextStartTimerFn start_timer_fn,
+    ForwardVtableContextStopTimerFn stop_timer_fn,
+    ForwardVtableContextGetAccumulatedTimeFn get_accumulated_time_fn) {
+  return new ForwardVtableContext(object_ptr, state, reset_log_fn, log_fn,
+                                  reset_timers_fn, start_timer_fn,
+                                  stop_timer_fn, get_accumulated_time_fn);
+}
+
+#endif
+
+// Detour definitions.
+#ifdef DETOUR
+
+unsigned char GetPolyArea(const dtPoly* poly) { return poly->getArea(); }
+
+unsigned char GetPolyType(const dtPoly* poly) { return poly->getType(); }
+
+dtPolyRef EncodePolyId(const dtNavMesh* nav_mesh, unsigned int salt,
+                       unsigned int tile_index, unsigned int poly_index) {
+  return nav_mesh->encodePolyId(salt, tile_index, poly_index);
+}
+
+void DecodePolyId(const dtNavMesh* nav_mesh, dtPolyRef ref,
+                  unsigned int* salt, unsigned int* tile_index,
+                  unsigned int* poly_index) {
+  nav_mesh->decodePolyId(ref, *salt, *tile_index, *poly_index);
+}
+
+namespace {
+
+// dtNavMeshQuery only accepts a plain function pointer for random numbers, so
+// the forwarded function and its object are stashed in thread-local storage
+// for the duration of the query.
+struct ForwardedRandom {
+  void* object_ptr;
+  ForwardRandomFn frand;
+};
+
+thread_local ForwardedRandom current_forwarded_random = {nullptr, nullptr};
+
+float CallCurrentForwardedRandom() {
+  return current_forwarded_random.frand(current_forwarded_random.object_ptr);
+}
+
+class ScopedForwardedRandom {
+ public:
+  ScopedForwardedRandom(void* object_ptr, ForwardRandomFn frand)
+      : previous_(current_forwarded_random) {
+    current_forwarded_random = {object_ptr, frand};
+  }
+
+  ~ScopedForwardedRandom() { current_forwarded_random = previous_; }
+
+ private:
+  ForwardedRandom previous_;
+};
+
+}  // namespace
+
+dtStatus FindRandomPointForwarded(const dtNavMeshQuery* query,
+                                  const dtQueryFilter* filter,
+                                  void* object_ptr, ForwardRandomFn frand,
+                                  dtPolyRef* randomRef, float* randomPt) {
+  ScopedForwardedRandom scoped_random(object_ptr, frand);
+  return query->findRandomPoint(filter, CallCurrentForwardedRandom, randomRef,
+                                randomPt);
+}
+
+dtStatus FindRandomPointAroundCircleForwarded(
+    const dtNavMeshQuery* query, dtPolyRef startRef, const float* centerPos,
+    const float maxRadius, const dtQueryFilter* filter, void* object_ptr,
+    ForwardRandomFn frand, dtPolyRef* randomRef, float* randomPt) {
+  ScopedForwardedRandom scoped_random(object_ptr, frand);
+  return query->findRandomPointAroundCircle(startRef, centerPos, maxRadius,
+                                            filter, CallCurrentForwardedRandom,
+                                            randomRef, randomPt);
+}
+
+class ForwardVtablePolyQuery : public dtPolyQuery {
+ public:
+  ForwardVtablePolyQuery(void* object_ptr,
+                         ForwardVtablePolyQueryProcessFn process_fn)
+      : object_ptr_(object_ptr), process_fn_(process_fn) {}
+
+  void process(const dtMeshTile* tile, dtPoly** polys, dtPolyRef* refs,
+               int count) override {
+    process_fn_(object_ptr_, tile, polys, refs, count);
+  }
+
+ private:
+  void* object_ptr_;
+  ForwardVtablePolyQueryProcessFn process_fn_;
+};
+
+dtPolyQuery* CreateForwardedPolyQuery(
+    void* object_ptr, ForwardVtablePolyQueryProcessFn process_fn) {
+  return new ForwardVtablePolyQuery(object_ptr, process_fn);
+}
+
+void DeletePolyQuery(dtPolyQuery* poly_query) { delete poly_query; }
+
+#endif
+
+// DetourCrowd definitions.
+#ifdef DETOUR_CROWD
+
+dtPathCorridor* CreatePathCorridor() { return new dtPathCorridor(); }
+
+void DeletePathCorridor(dtPathCorridor* corridor) { delete corridor; }
+
+const float* GetPathCorridorPos(const dtPathCorridor* corridor) {
+  return corridor->getPos();
+}
+
+const float* GetPathCorridorTarget(const dtPathCorridor* corridor) {
+  return corridor->getTarget();
+}
+
+dtPolyRef GetPathCorridorFirstPoly(const dtPathCorridor* corridor) {
+  return corridor->getFirstPoly();
+}
+
+dtPolyRef GetPathCorridorLastPoly(const dtPathCorridor* corridor) {
+  return corridor->getLastPoly();
+}
+
+const dtPolyRef* GetPathCorridorPath(const dtPathCorridor* corridor) {
+  return corridor->getPath();
+}
+
+int GetPathCorridorPathCount(const dtPathCorridor* corridor) {
+  return corridor->getPathCount();
+}
+
+dtLocalBoundary* CreateLocalBoundary() { return new dtLocalBoundary(); }
+
+void DeleteLocalBoundary(dtLocalBoundary* boundary) { delete boundary; }
+
+const float* GetLocalBoundaryCenter(const dtLocalBoundary* boundary) {
+  return boundary->getCenter();
+}
+
+int GetLocalBoundarySegmentCount(const dtLocalBoundary* boundary) {
+  return boundary->getSegmentCount();
+}
+
+const float* GetLocalBoundarySegment(const dtLocalBoundary* boundary, int i) {
+  return boundary->getSegment(i);
+}
+
+int GetObstacleCircleCount(const dtObstacleAvoidanceQuery* avoidance_query) {
+  return avoidance_query->getObstacleCircleCount();
+}
+
+int GetObstacleSegmentCount(const dtObstacleAvoidanceQuery* avoidance_query) {
+  return avoidance_query->getObstacleSegmentCount();
+}
+
+dtPathQueue* CreatePathQueue() { return new dtPathQueue(); }
+
+void DeletePathQueue(dtPathQueue* path_queue) { delete path_queue; }
+
 #endif
 
 // DetourTileCache definitions.
@@ -128,3 +345,119 @@ void DeleteTileCacheCompressor(dtTileCacheCompressor* compressor) {
 }
 
 #endif
+
+// DebugUtils definitions.
+#ifdef DEBUG_UTILS
+
+class ForwardVtableDebugDraw : public duDebugDraw {
+ public:
+  ForwardVtableDebugDraw(void* object_ptr,
+                         ForwardVtableDebugDrawDepthMaskFn depth_mask_fn,
+                         ForwardVtableDebugDrawTextureFn texture_fn,
+                         ForwardVtableDebugDrawBeginFn begin_fn,
+                         ForwardVtableDebugDrawVertexFn vertex_fn,
+                         ForwardVtableDebugDrawEndFn end_fn)
+      : object_ptr_(object_ptr),
+        depth_mask_fn_(depth_mask_fn),
+        texture_fn_(texture_fn),
+        begin_fn_(begin_fn),
+        vertex_fn_(vertex_fn),
+        end_fn_(end_fn) {}
+
+ private:
+  void depthMask(bool state) override { depth_mask_fn_(object_ptr_, state); }
+
+  void texture(bool state) override { texture_fn_(object_ptr_, state); }
+
+  void begin(duDebugDrawPrimitives prim, float size) override {
+    begin_fn_(object_ptr_, prim, size);
+  }
+
+  void vertex(const float* pos, unsigned int color) override {
+    vertex_fn_(object_ptr_, pos, color, nullptr);
+  }
+
+  void vertex(const float x, const float y, const float z,
+              unsigned int color) override {
+    const float pos[3] = {x, y, z};
+    vertex_fn_(object_ptr_, pos, color, nullptr);
+  }
+
+  void vertex(const float* pos, unsigned int color, const float* uv) override {
+    vertex_fn_(object_ptr_, pos, color, uv);
+  }
+
+  void vertex(const float x, const float y, const float z, unsigned int color,
+              const float u, const float v) override {
+    const float pos[3] = {x, y, z};
+    const float uv[2] = {u, v};
+    vertex_fn_(object_ptr_, pos, color, uv);
+  }
+
+  void end() override { end_fn_(object_ptr_); }
+
+  void* object_ptr_;
+  ForwardVtableDebugDrawDepthMaskFn depth_mask_fn_;
+  ForwardVtableDebugDrawTextureFn texture_fn_;
+  ForwardVtableDebugDrawBeginFn begin_fn_;
+  ForwardVtableDebugDrawVertexFn vertex_fn_;
+  ForwardVtableDebugDrawEndFn end_fn_;
+};
+
+duDebugDraw* CreateForwardedDebugDraw(
+    void* object_ptr, ForwardVtableDebugDrawDepthMaskFn depth_mask_fn,
+    ForwardVtableDebugDrawTextureFn texture_fn,
+    ForwardVtableDebugDrawBeginFn begin_fn,
+    ForwardVtableDebugDrawVertexFn vertex_fn,
+    ForwardVtableDebugDrawEndFn end_fn) {
+  return new ForwardVtableDebugDraw(object_ptr, depth_mask_fn, texture_fn,
+                                    begin_fn, vertex_fn, end_fn);
+}
+
+void DeleteDebugDraw(duDebugDraw* debug_draw) { delete debug_draw; }
+
+class ForwardVtableFileIO : public duFileIO {
+ public:
+  ForwardVtableFileIO(void* object_ptr,
+                      ForwardVtableFileIOIsWritingFn is_writing_fn,
+                      ForwardVtableFileIOIsReadingFn is_reading_fn,
+                      ForwardVtableFileIOWriteFn write_fn,
+                      ForwardVtableFileIOReadFn read_fn)
+      : object_ptr_(object_ptr),
+        is_writing_fn_(is_writing_fn),
+        is_reading_fn_(is_reading_fn),
+        write_fn_(write_fn),
+        read_fn_(read_fn) {}
+
+ private:
+  bool isWriting() const override { return is_writing_fn_(object_ptr_); }
+
+  bool isReading() const override { return is_reading_fn_(object_ptr_); }
+
+  bool write(const void* ptr, const size_t size) override {
+    return write_fn_(object_ptr_, ptr, size);
+  }
+
+  bool read(void* ptr, const size_t size) override {
+    return read_fn_(object_ptr_, ptr, size);
+  }
+
+  void* object_ptr_;
+  ForwardVtableFileIOIsWritingFn is_writing_fn_;
+  ForwardVtableFileIOIsReadingFn is_reading_fn_;
+  ForwardVtableFileIOWriteFn write_fn_;
+  ForwardVtableFileIOReadFn read_fn_;
+};
+
+duFileIO* CreateForwardedFileIO(void* object_ptr,
+                                ForwardVtableFileIOIsWritingFn is_writing_fn,
+                                ForwardVtableFileIOIsReadingFn is_reading_fn,
+                                ForwardVtableFileIOWriteFn write_fn,
+                                ForwardVtableFileIOReadFn read_fn) {
+  return new ForwardVtableFileIO(object_ptr, is_writing_fn, is_reading_fn,
+                                 write_fn, read_fn);
+}
+
+void DeleteFileIO(duFileIO* file_io) { delete file_io; }
+
+#endif
@@ -9,6 +9,117 @@ rcContext* CreateContext(bool state = true);
 
 void DeleteContext(rcContext* context);
 
+using ForwardVtableContextResetLogFn = void (*)(void* object_ptr);
+using ForwardVtableContextLogFn = void (*)(void* object_ptr,
+                                           rcLogCategory category,
+                                           const char* msg, int len);
+using ForwardVtableContextResetTimersFn = void (*)(void* object_ptr);
+using ForwardVtableContextStartTimerFn = void (*)(void* object_ptr,
+                                                  rcTimerLabel label);
+using ForwardVtableContextStopTimerFn = void (*)(void* object_ptr,
+                                                 rcTimerLabel label);
+using ForwardVtableContextGetAccumulatedTimeFn = int (*)(void* object_ptr,
+                                                         rcTimerLabel label);
+
+// Must be freed with DeleteContext, which also destroys the forwarding
+// subclass since rcContext has a virtual destructor.
+rcContext* CreateForwardedContext(
+    void* object_ptr, bool state, ForwardVtableContextResetLogFn reset_log_fn,
+    ForwardVtableContextLogFn log_fn,
+    ForwardVtableContextResetTimersFn reset_timers_fn,
+    ForwardVtableContextStartTimerFn start_timer_fn,
+    ForwardVtableContextStopTimerFn stop_timer_fn,
+    ForwardVtableContextGetAccumulatedTimeFn get_accumulated_time_fn);
+
+#endif
+
+// Detour declarations.
+#ifdef DETOUR
+
+#include "DetourNavMesh.h"
+#include "DetourNavMeshQuery.h"
+
+unsigned char GetPolyArea(const dtPoly* poly);
+
+unsigned char GetPolyType(const dtPoly* poly);
+
+dtPolyRef EncodePolyId(const dtNavMesh* nav_mesh, unsigned int salt,
+                       unsigned int tile_index, unsigned int poly_index);
+
+void DecodePolyId(const dtNavMesh* nav_mesh, dtPolyRef ref,
+                  unsigned int* salt, unsigned int* tile_index,
+                  unsigned int* poly_index);
+
+using ForwardRandomFn = float (*)(void* object_ptr);
+
+// Same as dtNavMeshQuery::findRandomPoint, except `frand` is called with
+// `object_ptr`, allowing for stateful random number generators.
+dtStatus FindRandomPointForwarded(const dtNavMeshQuery* query,
+                                  const dtQueryFilter* filter,
+                                  void* object_ptr, ForwardRandomFn frand,
+                                  dtPolyRef* randomRef, float* randomPt);
+
+// Same as dtNavMeshQuery::findRandomPointAroundCircle, except `frand` is
+// called with `object_ptr`, allowing for stateful random number generators.
+dtStatus FindRandomPointAroundCircleForwarded(
+    const dtNavMeshQuery* query, dtPolyRef startRef, const float* centerPos,
+    const float maxRadius, const dtQueryFilter* filter, void* object_ptr,
+    ForwardRandomFn frand, dtPolyRef* randomRef, float* randomPt);
+
+using ForwardVtablePolyQueryProcessFn = void (*)(void* object_ptr,
+                                                 const dtMeshTile* tile,
+                                                 dtPoly** polys,
+                                                 dtPolyRef* refs, int count);
+
+dtPolyQuery* CreateForwardedPolyQuery(
+    void* object_ptr, ForwardVtablePolyQueryProcessFn process_fn);
+
+void DeletePolyQuery(dtPolyQuery* poly_query);
+
+#endif
+
+// DetourCrowd declarations.
+#ifdef DETOUR_CROWD
+
+#include "DetourLocalBoundary.h"
+#include "DetourObstacleAvoidance.h"
+#include "DetourPathCorridor.h"
+#include "DetourPathQueue.h"
+
+dtPathCorridor* CreatePathCorridor();
+
+void DeletePathCorridor(dtPathCorridor* corridor);
+
+const float* GetPathCorridorPos(const dtPathCorridor* corridor);
+
+const float* GetPathCorridorTarget(const dtPathCorridor* corridor);
+
+dtPolyRef GetPathCorridorFirstPoly(const dtPathCorridor* corridor);
+
+dtPolyRef GetPathCorridorLastPoly(const dtPathCorridor* corridor);
+
+const dtPolyRef* GetPathCorridorPath(const dtPathCorridor* corridor);
+
+int GetPathCorridorPathCount(const dtPathCorridor* corridor);
+
+dtLocalBoundary* CreateLocalBoundary();
+
+void DeleteLocalBoundary(dtLocalBoundary* boundary);
+
+const float* GetLocalBoundaryCenter(const dtLocalBoundary* boundary);
+
+int GetLocalBoundarySegmentCount(const dtLocalBoundary* boundary);
+
+const float* GetLocalBoundarySegment(const dtLocalBoundary* boundary, int i);
+
+int GetObstacleCircleCount(const dtObstacleAvoidanceQuery* avoidance_query);
+
+int GetObstacleSegmentCount(const dtObstacleAvoidanceQuery* avoidance_query);
+
+dtPathQueue* CreatePathQueue();
+
+void DeletePathQueue(dtPathQueue* path_queue);
+
 #endif
 
 // DetourTileCache declarations.
@@ -61,3 +172,49 @@ dtTileCacheCompressor* CreateForwardedTileCacheCompressor(
 void DeleteTileCacheCompressor(dtTileCacheCompressor* compressor);
 
 #endif
+
+// DebugUtils declarations.
+#ifdef DEBUG_UTILS
+
+#include "DebugDraw.h"
+#include "RecastDump.h"
+
+using ForwardVtableDebugDrawDepthMaskFn = void (*)(void* object_ptr,
+                                                   bool state);
+using ForwardVtableDebugDrawTextureFn = void (*)(void* object_ptr, bool state);
+using ForwardVtableDebugDrawBeginFn = void (*)(void* object_ptr,
+                                               duDebugDrawPrimitives prim,
+                                               float size);
+// All `vertex` overloads are forwarded to this function. `uv` is null for the
+// overloads that do not provide texture coordinates.
+using ForwardVtableDebugDrawVertexFn = void (*)(void* object_ptr,
+                                                const float* pos,
+                                                unsigned int color,
+                                                const float* uv);
+using ForwardVtableDebugDrawEndFn = void (*)(void* object_ptr);
+
+duDebugDraw* CreateForwardedDebugDraw(
+    void* object_ptr, ForwardVtableDebugDrawDepthMaskFn depth_mask_fn,
+    ForwardVtableDebugDrawTextureFn texture_fn,
+    ForwardVtableDebugDrawBeginFn begin_fn,
+    ForwardVtableDebugDrawVertexFn vertex_fn,
+    ForwardVtableDebugDrawEndFn end_fn);
+
+void DeleteDebugDraw(duDebugDraw* debug_draw);
+
+using ForwardVtableFileIOIsWritingFn = bool (*)(void* object_ptr);
+using ForwardVtableFileIOIsReadingFn = bool (*)(void* object_ptr);
+using ForwardVtableFileIOWriteFn = bool (*)(void* object_ptr, const void* ptr,
+                                            const size_t size);
+using ForwardVtableFileIOReadFn = bool (*)(void* object_ptr, void* ptr,
+                                           const size_t size);
+
+duFileIO* CreateForwardedFileIO(void* object_ptr,
+                                ForwardVtableFileIOIsWritingFn is_writing_fn,
+                                ForwardVtableFileIOIsReadingFn is_reading_fn,
+                                ForwardVtableFileIOWriteFn write_fn,
+                                ForwardVtableFileIOReadFn read_fn);
+
+void DeleteFileIO(duFileIO* file_io);
+
+#endif
@@ -34,11 +34,25 @@ mod ffi_detour_tile_cache {
   include!(concat!(env!("OUT_DIR"), "/detour_tile_cache.rs"));
 }
 
-#[cfg(any(feature = "recast", feature = "detour_tile_cache"))]
+#[cfg(feature = "debug_utils")]
+#[allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+mod ffi_debug_utils {
+  use crate::ffi_detour::*;
+  use crate::ffi_detour_tile_cache::*;
+  use crate::ffi_recast::*;
+
+  include!(concat!(env!("OUT_DIR"), "/debug_utils.rs"));
+}
+
+#[cfg(any(feature = "recast", feature = "detour"))]
 #[allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
 mod ffi_inline {
+  #[cfg(feature = "debug_utils")]
+  use crate::ffi_debug_utils::*;
   #[cfg(feature = "detour")]
   use crate::ffi_detour::*;
+  #[cfg(feature = "detour_crowd")]
+  use crate::ffi_detour_crowd::*;
   #[cfg(feature = "detour_tile_cache")]
   use crate::ffi_detour_tile_cache::*;
   #[cfg(feature = "recast")]
@@ -47,13 +61,15 @@ mod ffi_inline {
   include!(concat!(env!("OUT_DIR"), "/inline.rs"));
 }
 
+#[cfg(feature = "debug_utils")]
+pub use ffi_debug_utils::*;
 #[cfg(feature = "detour")]
 pub use ffi_detour::*;
 #[cfg(feature = "detour_crowd")]
 pub use ffi_detour_crowd::*;
 #[cfg(feature = "detour_tile_cache")]
 pub use ffi_detour_tile_cache::*;
-#[cfg(any(feature = "recast", feature = "detour_tile_cache"))]
+#[cfg(any(feature = "recast", feature = "detour"))]
 pub use ffi_inline::*;
 #[cfg(feature = "recast")]
 pub use ffi_recast::*;
@@ -804,4 +820,3163 @@ mod tests {
     unsafe { DeleteTileCacheCompressor(forwarded_compressor) };
     unsafe { DeleteTileCacheAlloc(alloc) };
   }
+
+  /// Creates the params for a single tile made of `polys`, with no detail
+  /// meshes or off-mesh connections. The tile bounds default to zero and the
+  /// cell size and agent dimensions to one; tests override them (and add
+  /// detail meshes or off-mesh connections) with struct update syntax.
+  #[cfg(feature = "detour")]
+  fn create_nav_mesh_params(
+    verts: &[u16],
+    polys: &[u16],
+    poly_flags: &[u16],
+    poly_areas: &[u8],
+    nvp: i32,
+  ) -> dtNavMeshCreateParams {
+    dtNavMeshCreateParams {
+      verts: verts.as_ptr(),
+      vertCount: verts.len() as i32 / 3,
+      polys: polys.as_ptr(),
+      polyFlags: poly_flags.as_ptr(),
+      polyAreas: poly_areas.as_ptr(),
+      polyCount: polys.len() as i32 / (nvp * 2),
+      nvp,
+      detailMeshes: std::ptr::null(),
+      detailVerts: std::ptr::null(),
+      detailVertsCount: 0,
+      detailTris: std::ptr::null(),
+      detailTriCount: 0,
+      offMeshConVerts: std::ptr::null(),
+      offMeshConRad: std::ptr::null(),
+      offMeshConFlags: std::ptr::null(),
+      offMeshConAreas: std::ptr::null(),
+      offMeshConDir: std::ptr::null(),
+      offMeshConUserID: std::ptr::null(),
+      offMeshConCount: 0,
+      userId: 0,
+      tileX: 0,
+      tileY: 0,
+      tileLayer: 0,
+      bmin: [0.0, 0.0, 0.0],
+      bmax: [0.0, 0.0, 0.0],
+      walkableHeight: 1.0,
+      walkableRadius: 1.0,
+      walkableClimb: 1.0,
+      cs: 1.0,
+      ch: 1.0,
+      buildBvTree: false,
+    }
+  }
+
+  /// Creates tile data from `params`. The data is allocated with `dtAlloc`, so
+  /// it can be handed to a nav mesh with `DT_TILE_FREE_DATA`.
+  #[cfg(feature = "detour")]
+  fn create_nav_mesh_data(mut params: dtNavMeshCreateParams) -> (*mut u8, i32) {
+    let mut data: *mut u8 = std::ptr::null_mut();
+    let mut data_size: i32 = 0;
+
+    assert!(unsafe {
+      dtCreateNavMeshData(&mut params, &mut data, &mut data_size)
+    });
+
+    (data, data_size)
+  }
+
+  /// Creates a nav mesh with the single tile in `data`, which the nav mesh
+  /// takes ownership of. Must be freed with `dtFreeNavMesh`.
+  #[cfg(feature = "detour")]
+  fn create_nav_mesh(data: *mut u8, data_size: i32) -> *mut dtNavMesh {
+    let nav_mesh = unsafe { dtAllocNavMesh() };
+    assert_ne!(nav_mesh, std::ptr::null_mut());
+    assert_eq!(
+      unsafe {
+        (*nav_mesh).init1(data, data_size, dtTileFlags_DT_TILE_FREE_DATA as i32)
+      },
+      DT_SUCCESS
+    );
+
+    nav_mesh
+  }
+
+  /// Creates the tile data for the nav mesh used in `detour_finds_simple_path`.
+  /// The data is allocated with `dtAlloc`, so it can be handed to a nav mesh
+  /// with `DT_TILE_FREE_DATA`.
+  #[cfg(feature = "detour")]
+  fn create_simple_nav_mesh_data() -> (*mut u8, i32) {
+    let verts = [
+      1, 0, 1, //
+      1, 0, 0, //
+      2, 0, 0, //
+      2, 0, 1, //
+      3, 0, 1, //
+      3, 0, 2, //
+      2, 0, 2, //
+      1, 0, 2, //
+      0, 0, 2, //
+      0, 0, 1, //
+    ];
+
+    const N: u16 = 0xffff;
+
+    let polys = [
+      0, 1, 2, N, N, 1, //
+      2, 3, 0, N, 2, 0, //
+      0, 3, 6, 1, 4, 3, //
+      0, 6, 7, 2, N, 6, //
+      6, 3, 4, 2, N, 5, //
+      6, 4, 5, 4, N, N, //
+      0, 7, 8, 3, N, 7, //
+      0, 8, 9, 6, N, N, //
+    ];
+
+    create_nav_mesh_data(dtNavMeshCreateParams {
+      bmax: [3.0, 2.0, 2.0],
+      ..create_nav_mesh_params(
+        &verts, &polys, &[1; 8], &[0; 8], /*nvp=*/ 3,
+      )
+    })
+  }
+
+  /// Creates the nav mesh used in `detour_finds_simple_path`. Must be freed
+  /// with `dtFreeNavMesh`.
+  #[cfg(feature = "detour")]
+  fn create_simple_nav_mesh() -> *mut dtNavMesh {
+    let (data, data_size) = create_simple_nav_mesh_data();
+    create_nav_mesh(data, data_size)
+  }
+
+  /// Creates the nav mesh used in `detour_finds_simple_path` along with a query
+  /// initialised for it. Must be freed with `dtFreeNavMeshQuery` and
+  /// `dtFreeNavMesh`.
+  #[cfg(feature = "detour")]
+  fn create_simple_nav_mesh_and_query(
+  ) -> (*mut dtNavMesh, &'static mut dtNavMeshQuery) {
+    let nav_mesh = create_simple_nav_mesh();
+    let query = unsafe { &mut *dtAllocNavMeshQuery() };
+    assert_eq!(unsafe { query.init(nav_mesh, 512) }, DT_SUCCESS);
+
+    (nav_mesh, query)
+  }
+
+  #[cfg(feature = "detour")]
+  fn create_query_filter() -> dtQueryFilter {
+    dtQueryFilter {
+      m_areaCost: [1.0; 64],
+      m_includeFlags: 0xffff,
+      m_excludeFlags: 0,
+    }
+  }
+
+  #[cfg(feature = "detour")]
+  fn find_nearest_poly(query: &dtNavMeshQuery, pos: &[f32; 3]) -> dtPolyRef {
+    let extents = [0.1, 100.0, 0.1];
+
+    let mut poly_ref: dtPolyRef = 0;
+
+    assert_eq!(
+      unsafe {
+        query.findNearestPoly(
+          pos.as_ptr(),
+          extents.as_ptr(),
+          &create_query_filter(),
+          &mut poly_ref,
+          std::ptr::null_mut(),
+        )
+      },
+      DT_SUCCESS
+    );
+
+    poly_ref
+  }
+
+  #[cfg(feature = "detour")]
+  fn find_path(
+    query: &dtNavMeshQuery,
+    start_point: &[f32; 3],
+    end_point: &[f32; 3],
+  ) -> Vec<dtPolyRef> {
+    let mut path = [0; 10];
+    let mut path_count = 0;
+
+    assert_eq!(
+      unsafe {
+        query.findPath(
+          find_nearest_poly(query, start_point),
+          find_nearest_poly(query, end_point),
+          start_point.as_ptr(),
+          end_point.as_ptr(),
+          &create_query_filter(),
+          path.as_mut_ptr(),
+          &mut path_count,
+          path.len() as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+
+    path[..path_count as usize].to_vec()
+  }
+
+  #[cfg(feature = "detour")]
+  fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let delta = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    (delta[0] * delta[0] + delta[1] * delta[1] + delta[2] * delta[2]).sqrt()
+  }
+
+  #[cfg(feature = "detour_crowd")]
+  #[test]
+  fn detour_crowd_path_corridor_follows_path() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    let start_point = [1.1, 0.0, 0.1];
+    let end_point = [2.9, 0.0, 1.9];
+
+    let path = find_path(query, &start_point, &end_point);
+    assert_eq!(path.len(), 5);
+
+    let corridor = unsafe { &mut *CreatePathCorridor() };
+    assert!(unsafe {
+      corridor.init(/*maxPath=*/ 10)
+    });
+
+    unsafe { corridor.reset(path[0], start_point.as_ptr()) };
+    unsafe {
+      corridor.setCorridor(end_point.as_ptr(), path.as_ptr(), path.len() as i32)
+    };
+    assert_eq!(unsafe { GetPathCorridorPathCount(corridor) }, 5);
+    assert_eq!(unsafe { GetPathCorridorFirstPoly(corridor) }, path[0]);
+    assert_eq!(unsafe { GetPathCorridorLastPoly(corridor) }, path[4]);
+    assert_eq!(
+      unsafe { *(GetPathCorridorTarget(corridor) as *const [f32; 3]) },
+      end_point
+    );
+
+    // Walk towards the next corner in fixed steps until the corridor reports
+    // no more corners (i.e. the target has been reached).
+    const STEP: f32 = 0.1;
+    for _ in 0..100 {
+      let mut corner_verts = [0.0; 3 * 3];
+      let mut corner_flags = [0; 3];
+      let mut corner_polys = [0; 3];
+
+      let corner_count = unsafe {
+        corridor.findCorners(
+          corner_verts.as_mut_ptr(),
+          corner_flags.as_mut_ptr(),
+          corner_polys.as_mut_ptr(),
+          corner_flags.len() as i32,
+          query,
+          &query_filter,
+        )
+      };
+      if corner_count == 0 {
+        break;
+      }
+
+      let position =
+        unsafe { *(GetPathCorridorPos(corridor) as *const [f32; 3]) };
+      let corner = [corner_verts[0], corner_verts[1], corner_verts[2]];
+      let t = (STEP / distance(&position, &corner)).min(1.0);
+      let new_position = [
+        position[0] + (corner[0] - position[0]) * t,
+        position[1] + (corner[1] - position[1]) * t,
+        position[2] + (corner[2] - position[2]) * t,
+      ];
+
+      unsafe {
+        corridor.movePosition(new_position.as_ptr(), query, &query_filter)
+      };
+    }
+
+    let position =
+      unsafe { *(GetPathCorridorPos(corridor) as *const [f32; 3]) };
+    assert!(
+      distance(&position, &end_point) < 0.01,
+      "\n\nleft: {:?}\nright: {:?}",
+      position,
+      end_point
+    );
+    assert_eq!(unsafe { GetPathCorridorPathCount(corridor) }, 1);
+    assert_eq!(unsafe { *GetPathCorridorPath(corridor) }, path[4]);
+
+    unsafe { DeletePathCorridor(corridor) };
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour_crowd")]
+  #[test]
+  fn detour_crowd_path_queue_finds_path() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    let start_point = [1.1, 0.0, 0.1];
+    let end_point = [2.9, 0.0, 1.9];
+
+    let start_poly_ref = find_nearest_poly(query, &start_point);
+    let end_poly_ref = find_nearest_poly(query, &end_point);
+
+    let path_queue = unsafe { &mut *CreatePathQueue() };
+    assert!(unsafe {
+      path_queue.init(
+        /*maxPathSize=*/ 10, /*maxSearchNodeCount=*/ 512, nav_mesh,
+      )
+    });
+
+    let request = unsafe {
+      path_queue.request(
+        start_poly_ref,
+        end_poly_ref,
+        start_point.as_ptr(),
+        end_point.as_ptr(),
+        &query_filter,
+      )
+    };
+    assert_ne!(request, DT_PATHQ_INVALID);
+
+    // Only allow a single iteration per update to make sure the request is
+    // actually spread across multiple updates.
+    let mut status = unsafe { path_queue.getRequestStatus(request) };
+    let mut updates = 0;
+    while status & DT_SUCCESS == 0 {
+      assert_eq!(status & DT_FAILURE, 0, "status: {:#x}", status);
+      assert!(updates < 100, "Path request did not finish.");
+
+      unsafe {
+        path_queue.update(/*maxIters=*/ 1)
+      };
+      status = unsafe { path_queue.getRequestStatus(request) };
+      updates += 1;
+    }
+    assert!(updates > 1);
+
+    let mut path = [0; 10];
+    let mut path_count = 0;
+    assert_eq!(
+      unsafe {
+        path_queue.getPathResult(
+          request,
+          path.as_mut_ptr(),
+          &mut path_count,
+          path.len() as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+
+    assert_eq!(
+      path[..path_count as usize]
+        .iter()
+        .map(|polyref| polyref & 0b111)
+        .collect::<Vec<_>>(),
+      [0, 1, 2, 4, 5]
+    );
+
+    // Once the result has been fetched, the request is gone.
+    assert_eq!(unsafe { path_queue.getRequestStatus(request) }, DT_FAILURE);
+
+    unsafe { DeletePathQueue(path_queue) };
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour_crowd")]
+  #[test]
+  fn detour_crowd_local_boundary_collects_walls() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    let position = [1.1, 0.0, 0.1];
+    let poly_ref = find_nearest_poly(query, &position);
+
+    let boundary = unsafe { &mut *CreateLocalBoundary() };
+    assert_eq!(unsafe { GetLocalBoundarySegmentCount(boundary) }, 0);
+
+    unsafe {
+      boundary.update(
+        poly_ref,
+        position.as_ptr(),
+        /*collisionQueryRange=*/ 1.0,
+        query,
+        &query_filter,
+      )
+    };
+
+    assert_eq!(
+      unsafe { *(GetLocalBoundaryCenter(boundary) as *const [f32; 3]) },
+      position
+    );
+    let segment_count = unsafe { GetLocalBoundarySegmentCount(boundary) };
+    assert!(segment_count > 0);
+    // Every wall is an edge of the mesh, which lies flat from (0,0) to (3,2).
+    for i in 0..segment_count {
+      let segment =
+        unsafe { *(GetLocalBoundarySegment(boundary, i) as *const [f32; 6]) };
+      for point in segment.chunks(3) {
+        assert!((0.0..=3.0).contains(&point[0]), "{segment:?}");
+        assert_eq!(point[1], 0.0, "{segment:?}");
+        assert!((0.0..=2.0).contains(&point[2]), "{segment:?}");
+      }
+    }
+    assert!(unsafe { boundary.isValid(query, &query_filter) });
+
+    unsafe { boundary.reset() };
+    assert_eq!(unsafe { GetLocalBoundarySegmentCount(boundary) }, 0);
+
+    unsafe { DeleteLocalBoundary(boundary) };
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour_crowd")]
+  #[test]
+  fn detour_crowd_proximity_grid_queries_items() {
+    let grid = unsafe { &mut *dtAllocProximityGrid() };
+    assert!(unsafe {
+      grid.init(/*poolSize=*/ 10, /*cellSize=*/ 1.0)
+    });
+
+    unsafe { grid.addItem(0, 0.0, 0.0, 0.5, 0.5) };
+    unsafe { grid.addItem(1, 1.5, 1.5, 2.5, 2.5) };
+    unsafe { grid.addItem(2, 8.0, 8.0, 9.0, 9.0) };
+
+    let mut ids = [0; 10];
+    let id_count = unsafe {
+      grid.queryItems(0.0, 0.0, 2.0, 2.0, ids.as_mut_ptr(), ids.len() as i32)
+    };
+
+    let mut found_ids = ids[..id_count as usize].to_vec();
+    found_ids.sort();
+    assert_eq!(found_ids, [0, 1]);
+
+    unsafe { grid.clear() };
+    assert_eq!(
+      unsafe {
+        grid.queryItems(0.0, 0.0, 2.0, 2.0, ids.as_mut_ptr(), ids.len() as i32)
+      },
+      0
+    );
+
+    unsafe { dtFreeProximityGrid(grid) };
+  }
+
+  #[cfg(feature = "detour_crowd")]
+  #[test]
+  fn detour_crowd_obstacle_avoidance_avoids_obstacles() {
+    let avoidance_query = unsafe { &mut *dtAllocObstacleAvoidanceQuery() };
+    assert!(unsafe {
+      avoidance_query.init(/*maxCircles=*/ 6, /*maxSegments=*/ 8)
+    });
+
+    // These match the defaults used by dtCrowd.
+    let params = dtObstacleAvoidanceParams {
+      velBias: 0.4,
+      weightDesVel: 2.0,
+      weightCurVel: 0.75,
+      weightSide: 0.75,
+      weightToi: 2.5,
+      horizTime: 2.5,
+      gridSize: 33,
+      adaptiveDivs: 7,
+      adaptiveRings: 2,
+      adaptiveDepth: 5,
+    };
+
+    let position = [0.0, 0.0, 0.0];
+    let radius = 0.5;
+    let max_speed = 1.0;
+    let velocity = [0.0, 0.0, 0.0];
+    let desired_velocity = [1.0, 0.0, 0.0];
+
+    // Samples a new velocity with both the grid and the adaptive sampling.
+    let sample_velocities = |avoidance_query: &mut dtObstacleAvoidanceQuery| {
+      let mut grid_velocity = [0.0; 3];
+      let grid_sample_count = unsafe {
+        avoidance_query.sampleVelocityGrid(
+          position.as_ptr(),
+          radius,
+          max_speed,
+          velocity.as_ptr(),
+          desired_velocity.as_ptr(),
+          grid_velocity.as_mut_ptr(),
+          &params,
+          std::ptr::null_mut(),
+        )
+      };
+      assert!(grid_sample_count > 0);
+
+      let mut adaptive_velocity = [0.0; 3];
+      let adaptive_sample_count = unsafe {
+        avoidance_query.sampleVelocityAdaptive(
+          position.as_ptr(),
+          radius,
+          max_speed,
+          velocity.as_ptr(),
+          desired_velocity.as_ptr(),
+          adaptive_velocity.as_mut_ptr(),
+          &params,
+          std::ptr::null_mut(),
+        )
+      };
+      assert!(adaptive_sample_count > 0);
+
+      [grid_velocity, adaptive_velocity]
+    };
+
+    // With nothing in the way, the desired velocity is kept.
+    unsafe { avoidance_query.reset() };
+    for new_velocity in sample_velocities(avoidance_query) {
+      assert!(
+        distance(&new_velocity, &desired_velocity) < 0.1,
+        "\n\nleft: {:?}\nright: {:?}",
+        new_velocity,
+        desired_velocity
+      );
+    }
+
+    // A static circle directly ahead forces a different velocity.
+    unsafe { avoidance_query.reset() };
+    unsafe {
+      avoidance_query.addCircle(
+        [2.0, 0.0, 0.0].as_ptr(),
+        /*rad=*/ 0.5,
+        [0.0, 0.0, 0.0].as_ptr(),
+        [0.0, 0.0, 0.0].as_ptr(),
+      )
+    };
+    assert_eq!(unsafe { GetObstacleCircleCount(avoidance_query) }, 1);
+    assert_eq!(unsafe { GetObstacleSegmentCount(avoidance_query) }, 0);
+
+    for new_velocity in sample_velocities(avoidance_query) {
+      assert!(
+        distance(&new_velocity, &desired_velocity) > 0.1,
+        "\n\nleft: {:?}\nright: {:?}",
+        new_velocity,
+        desired_velocity
+      );
+    }
+
+    // So does a wall across the way.
+    unsafe { avoidance_query.reset() };
+    unsafe {
+      avoidance_query
+        .addSegment([1.0, 0.0, -2.0].as_ptr(), [1.0, 0.0, 2.0].as_ptr())
+    };
+    assert_eq!(unsafe { GetObstacleCircleCount(avoidance_query) }, 0);
+    assert_eq!(unsafe { GetObstacleSegmentCount(avoidance_query) }, 1);
+
+    for new_velocity in sample_velocities(avoidance_query) {
+      assert!(
+        distance(&new_velocity, &desired_velocity) > 0.1,
+        "\n\nleft: {:?}\nright: {:?}",
+        new_velocity,
+        desired_velocity
+      );
+    }
+
+    unsafe { dtFreeObstacleAvoidanceQuery(avoidance_query) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_finds_sliced_path() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    let start_point = [1.1, 0.0, 0.1];
+    let end_point = [2.9, 0.0, 1.9];
+
+    let start_poly_ref = find_nearest_poly(query, &start_point);
+    let end_poly_ref = find_nearest_poly(query, &end_point);
+
+    assert_eq!(
+      unsafe {
+        query.initSlicedFindPath(
+          start_poly_ref,
+          end_poly_ref,
+          start_point.as_ptr(),
+          end_point.as_ptr(),
+          &query_filter,
+          /*options=*/ 0,
+        )
+      },
+      DT_IN_PROGRESS
+    );
+
+    // Step a single iteration at a time so the search spans several updates.
+    let mut status = DT_IN_PROGRESS;
+    let mut updates = 0;
+    while status & DT_IN_PROGRESS != 0 {
+      assert!(updates < 100, "Sliced path search did not finish.");
+
+      let mut done_iterations = 0;
+      status = unsafe { query.updateSlicedFindPath(1, &mut done_iterations) };
+      assert_eq!(done_iterations, 1);
+      updates += 1;
+    }
+    assert_eq!(status, DT_SUCCESS);
+    assert!(updates > 1);
+
+    let mut path = [0; 10];
+    let mut path_count = 0;
+
+    assert_eq!(
+      unsafe {
+        query.finalizeSlicedFindPath(
+          path.as_mut_ptr(),
+          &mut path_count,
+          path.len() as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+
+    let full_path = path[..path_count as usize].to_vec();
+    assert_eq!(
+      full_path.iter().map(|polyref| polyref & 0b111).collect::<Vec<_>>(),
+      [0, 1, 2, 4, 5]
+    );
+
+    // Stop a new search early and salvage the part of the old path that was
+    // reached so far.
+    assert_eq!(
+      unsafe {
+        query.initSlicedFindPath(
+          start_poly_ref,
+          end_poly_ref,
+          start_point.as_ptr(),
+          end_point.as_ptr(),
+          &query_filter,
+          /*options=*/ 0,
+        )
+      },
+      DT_IN_PROGRESS
+    );
+
+    let mut done_iterations = 0;
+    assert_eq!(
+      unsafe { query.updateSlicedFindPath(1, &mut done_iterations) },
+      DT_IN_PROGRESS
+    );
+
+    let status = unsafe {
+      query.finalizeSlicedFindPathPartial(
+        full_path.as_ptr(),
+        full_path.len() as i32,
+        path.as_mut_ptr(),
+        &mut path_count,
+        path.len() as i32,
+      )
+    };
+    assert_ne!(status & DT_SUCCESS, 0, "status: {:#x}", status);
+    assert!(path_count > 0);
+    assert!(path_count < full_path.len() as i32);
+    assert_eq!(path[..path_count as usize], full_path[..path_count as usize]);
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_sliced_queries_share_nav_mesh() {
+    let nav_mesh = create_simple_nav_mesh();
+
+    let queries = (0..3)
+      .map(|_| {
+        let query = unsafe { dtAllocNavMeshQuery() };
+        assert_eq!(unsafe { (*query).init(nav_mesh, 512) }, DT_SUCCESS);
+        query
+      })
+      .collect::<Vec<_>>();
+
+    let query_filter = create_query_filter();
+
+    let init_request = |query: *mut dtNavMeshQuery,
+                        start_point: &[f32; 3],
+                        end_point: &[f32; 3]| {
+      let query = unsafe { &mut *query };
+      let start_poly_ref = find_nearest_poly(query, start_point);
+      let end_poly_ref = find_nearest_poly(query, end_point);
+      assert_eq!(
+        unsafe {
+          query.initSlicedFindPath(
+            start_poly_ref,
+            end_poly_ref,
+            start_point.as_ptr(),
+            end_point.as_ptr(),
+            &query_filter,
+            /*options=*/ 0,
+          )
+        },
+        DT_IN_PROGRESS
+      );
+    };
+
+    let start_point = [1.1, 0.0, 0.1];
+    let end_point = [2.9, 0.0, 1.9];
+    for query in queries.iter() {
+      init_request(*query, &start_point, &end_point);
+    }
+
+    // Spend one iteration per query per tick, so requests are interleaved over
+    // the same nav mesh.
+    let mut statuses = vec![DT_IN_PROGRESS; queries.len()];
+    let tick = |statuses: &mut Vec<u32>| {
+      for (query, status) in queries.iter().zip(statuses.iter_mut()) {
+        if *status & DT_IN_PROGRESS == 0 {
+          continue;
+        }
+        let mut done_iterations = 0;
+        *status =
+          unsafe { (**query).updateSlicedFindPath(1, &mut done_iterations) };
+      }
+    };
+
+    tick(&mut statuses);
+
+    // "Cancel" the last request by starting a different one on its query.
+    init_request(queries[2], &end_point, &start_point);
+    statuses[2] = DT_IN_PROGRESS;
+
+    for _ in 0..100 {
+      if statuses.iter().all(|status| *status & DT_IN_PROGRESS == 0) {
+        break;
+      }
+      tick(&mut statuses);
+    }
+    assert_eq!(statuses, [DT_SUCCESS, DT_SUCCESS, DT_SUCCESS]);
+
+    let paths = queries
+      .iter()
+      .map(|query| {
+        let mut path = [0; 10];
+        let mut path_count = 0;
+        assert_eq!(
+          unsafe {
+            (**query).finalizeSlicedFindPath(
+              path.as_mut_ptr(),
+              &mut path_count,
+              path.len() as i32,
+            )
+          },
+          DT_SUCCESS
+        );
+        path[..path_count as usize]
+          .iter()
+          .map(|polyref| polyref & 0b111)
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      paths,
+      [vec![0, 1, 2, 4, 5], vec![0, 1, 2, 4, 5], vec![5, 4, 2, 1, 0]]
+    );
+
+    for query in queries {
+      unsafe { dtFreeNavMeshQuery(query) };
+    }
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_finds_straight_path() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let start_point = [1.1, 0.0, 0.1];
+    let end_point = [2.9, 0.0, 1.9];
+
+    let path = find_path(query, &start_point, &end_point);
+    assert_eq!(path.len(), 5);
+
+    let find_straight_path = |options: u32| {
+      const MAX_POINTS: usize = 10;
+      let mut straight_path = [0.0; MAX_POINTS * 3];
+      let mut straight_path_flags = [0; MAX_POINTS];
+      let mut straight_path_refs = [0; MAX_POINTS];
+      let mut straight_path_count = 0;
+
+      assert_eq!(
+        unsafe {
+          query.findStraightPath(
+            start_point.as_ptr(),
+            end_point.as_ptr(),
+            path.as_ptr(),
+            path.len() as i32,
+            straight_path.as_mut_ptr(),
+            straight_path_flags.as_mut_ptr(),
+            straight_path_refs.as_mut_ptr(),
+            &mut straight_path_count,
+            MAX_POINTS as i32,
+            options as i32,
+          )
+        },
+        DT_SUCCESS
+      );
+
+      let straight_path_count = straight_path_count as usize;
+      let points = straight_path[..straight_path_count * 3]
+        .chunks_exact(3)
+        .map(|point| [point[0], point[1], point[2]])
+        .collect::<Vec<_>>();
+      (
+        points,
+        straight_path_flags[..straight_path_count].to_vec(),
+        straight_path_refs[..straight_path_count].to_vec(),
+      )
+    };
+
+    let (points, flags, refs) = find_straight_path(0);
+    assert!(points.len() >= 2);
+
+    assert_eq!(points[0], start_point);
+    assert_eq!(flags[0], dtStraightPathFlags_DT_STRAIGHTPATH_START as u8);
+    assert_eq!(refs[0], path[0]);
+
+    assert_eq!(points[points.len() - 1], end_point);
+    assert_eq!(
+      flags[flags.len() - 1],
+      dtStraightPathFlags_DT_STRAIGHTPATH_END as u8
+    );
+    // The end point does not belong to a polygon.
+    assert_eq!(refs[refs.len() - 1], 0);
+
+    // All polys share the same area, so there are no area crossings to add.
+    let (area_crossing_points, _, _) =
+      find_straight_path(dtStraightPathOptions_DT_STRAIGHTPATH_AREA_CROSSINGS);
+    assert_eq!(area_crossing_points, points);
+
+    // Every polygon edge crossed adds a point.
+    let (all_crossing_points, _, all_crossing_refs) =
+      find_straight_path(dtStraightPathOptions_DT_STRAIGHTPATH_ALL_CROSSINGS);
+    assert!(
+      all_crossing_points.len() > points.len(),
+      "\n\nleft: {:?}\nright: {:?}",
+      all_crossing_points,
+      points
+    );
+    assert_eq!(all_crossing_points[0], start_point);
+    assert_eq!(all_crossing_points[all_crossing_points.len() - 1], end_point);
+    assert!(all_crossing_refs
+      .iter()
+      .all(|poly_ref| *poly_ref == 0 || path.contains(poly_ref)));
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_smooth_path_crosses_off_mesh_connection() {
+    // Two disconnected squares, the second one raised, joined by a one-way
+    // off-mesh connection.
+    let verts = [
+      0, 0, 2, //
+      0, 0, 0, //
+      2, 0, 0, //
+      2, 0, 2, //
+      4, 1, 2, //
+      4, 1, 0, //
+      6, 1, 0, //
+      6, 1, 2, //
+    ];
+
+    const N: u16 = 0xffff;
+
+    let polys = [
+      0, 1, 2, 3, N, N, N, N, //
+      4, 5, 6, 7, N, N, N, N, //
+    ];
+
+    let off_mesh_connection_verts = [0.9, 0.0, 0.5, 2.1, 0.5, 0.5];
+    let off_mesh_connection_radii = [0.1];
+    let off_mesh_connection_flags = [1];
+    let off_mesh_connection_areas = [0];
+    let off_mesh_connection_dirs = [0];
+    let off_mesh_connection_user_ids = [0];
+
+    let (data, data_size) = create_nav_mesh_data(dtNavMeshCreateParams {
+      offMeshConVerts: off_mesh_connection_verts.as_ptr(),
+      offMeshConRad: off_mesh_connection_radii.as_ptr(),
+      offMeshConFlags: off_mesh_connection_flags.as_ptr(),
+      offMeshConAreas: off_mesh_connection_areas.as_ptr(),
+      offMeshConDir: off_mesh_connection_dirs.as_ptr(),
+      offMeshConUserID: off_mesh_connection_user_ids.as_ptr(),
+      offMeshConCount: 1,
+      bmax: [3.0, 0.5, 1.0],
+      walkableRadius: 0.5,
+      walkableClimb: 0.5,
+      cs: 0.5,
+      ch: 0.5,
+      ..create_nav_mesh_params(
+        &verts,
+        &polys,
+        &[1, 1],
+        &[0, 0],
+        /*nvp=*/ 4,
+      )
+    });
+    let nav_mesh = unsafe { &mut *create_nav_mesh(data, data_size) };
+
+    let query = unsafe { &mut *dtAllocNavMeshQuery() };
+    assert_eq!(unsafe { query.init(nav_mesh, 512) }, DT_SUCCESS);
+
+    let query_filter = create_query_filter();
+
+    let start_point = [0.1, 0.0, 0.5];
+    let end_point = [2.9, 0.5, 0.5];
+
+    // The corridor goes through the off-mesh connection's poly.
+    let mut polys = find_path(query, &start_point, &end_point);
+    assert_eq!(polys.len(), 3);
+
+    // This follows the "smooth path" mode of RecastDemo's NavMeshTesterTool.
+    const STEP_SIZE: f32 = 0.1;
+    const SLOP: f32 = 0.01;
+
+    fn in_range(a: &[f32; 3], b: &[f32; 3], radius: f32, height: f32) -> bool {
+      let dx = b[0] - a[0];
+      let dy = b[1] - a[1];
+      let dz = b[2] - a[2];
+      dx * dx + dz * dz < radius * radius && dy.abs() < height
+    }
+
+    let mut iter_position = start_point;
+    let mut smooth_path = vec![iter_position];
+    let mut off_mesh_connection_end_points = None;
+
+    for _ in 0..1000 {
+      assert!(!polys.is_empty());
+
+      // Find the next steer target, skipping any points that have already
+      // been reached (except for the start of off-mesh connections).
+      let mut steer_path = [0.0; 3 * 3];
+      let mut steer_path_flags = [0; 3];
+      let mut steer_path_refs = [0; 3];
+      let mut steer_path_count = 0;
+      let status = unsafe {
+        query.findStraightPath(
+          iter_position.as_ptr(),
+          end_point.as_ptr(),
+          polys.as_ptr(),
+          polys.len() as i32,
+          steer_path.as_mut_ptr(),
+          steer_path_flags.as_mut_ptr(),
+          steer_path_refs.as_mut_ptr(),
+          &mut steer_path_count,
+          steer_path_flags.len() as i32,
+          /*options=*/ 0,
+        )
+      };
+      assert_ne!(status & DT_SUCCESS, 0, "status: {:#x}", status);
+
+      let steer_index = (0..steer_path_count as usize)
+        .find(|&i| {
+          steer_path_flags[i]
+            & dtStraightPathFlags_DT_STRAIGHTPATH_OFFMESH_CONNECTION as u8
+            != 0
+            || !in_range(
+              &[
+                steer_path[i * 3],
+                steer_path[i * 3 + 1],
+                steer_path[i * 3 + 2],
+              ],
+              &iter_position,
+              SLOP,
+              1000.0,
+            )
+        })
+        .expect("A steer target should remain.");
+      let steer_position = [
+        steer_path[steer_index * 3],
+        steer_path[steer_index * 3 + 1],
+        steer_path[steer_index * 3 + 2],
+      ];
+      let end_of_path = steer_path_flags[steer_index]
+        & dtStraightPathFlags_DT_STRAIGHTPATH_END as u8
+        != 0;
+      let off_mesh_connection = steer_path_flags[steer_index]
+        & dtStraightPathFlags_DT_STRAIGHTPATH_OFFMESH_CONNECTION as u8
+        != 0;
+
+      // Move towards the steer target, but do not overshoot the end of the
+      // path or the start of an off-mesh connection.
+      let steer_distance = distance(&steer_position, &iter_position);
+      let scale =
+        if (end_of_path || off_mesh_connection) && steer_distance < STEP_SIZE {
+          1.0
+        } else {
+          STEP_SIZE / steer_distance
+        };
+      let move_target = [
+        iter_position[0] + (steer_position[0] - iter_position[0]) * scale,
+        iter_position[1] + (steer_position[1] - iter_position[1]) * scale,
+        iter_position[2] + (steer_position[2] - iter_position[2]) * scale,
+      ];
+
+      let mut result = [0.0; 3];
+      let mut visited = [0; 16];
+      let mut visited_count = 0;
+      assert_eq!(
+        unsafe {
+          query.moveAlongSurface(
+            polys[0],
+            iter_position.as_ptr(),
+            move_target.as_ptr(),
+            &query_filter,
+            result.as_mut_ptr(),
+            visited.as_mut_ptr(),
+            &mut visited_count,
+            visited.len() as i32,
+          )
+        },
+        DT_SUCCESS
+      );
+
+      // Drop the polys that were moved past from the start of the corridor.
+      if let Some(furthest_index) = visited[..visited_count as usize]
+        .iter()
+        .rev()
+        .find_map(|visited| polys.iter().position(|poly| poly == visited))
+      {
+        polys.drain(..furthest_index);
+      }
+
+      let mut height = 0.0;
+      assert_eq!(
+        unsafe { query.getPolyHeight(polys[0], result.as_ptr(), &mut height) },
+        DT_SUCCESS
+      );
+      result[1] = height;
+      iter_position = result;
+
+      if end_of_path && in_range(&iter_position, &steer_position, SLOP, 1.0) {
+        smooth_path.push(steer_position);
+        break;
+      }
+
+      if off_mesh_connection
+        && in_range(&iter_position, &steer_position, SLOP, 1.0)
+      {
+        // Advance the corridor over the off-mesh connection and jump to its
+        // end point.
+        let off_mesh_connection_index = polys
+          .iter()
+          .position(|poly| *poly == steer_path_refs[steer_index])
+          .expect("The off-mesh connection should be in the corridor.");
+        assert!(off_mesh_connection_index > 0);
+
+        let mut connection_start = [0.0; 3];
+        let mut connection_end = [0.0; 3];
+        assert_eq!(
+          unsafe {
+            nav_mesh.getOffMeshConnectionPolyEndPoints(
+              polys[off_mesh_connection_index - 1],
+              polys[off_mesh_connection_index],
+              connection_start.as_mut_ptr(),
+              connection_end.as_mut_ptr(),
+            )
+          },
+          DT_SUCCESS
+        );
+        polys.drain(..=off_mesh_connection_index);
+        off_mesh_connection_end_points =
+          Some((connection_start, connection_end));
+
+        smooth_path.push(connection_start);
+        iter_position = connection_end;
+
+        let mut height = 0.0;
+        assert_eq!(
+          unsafe {
+            query.getPolyHeight(polys[0], iter_position.as_ptr(), &mut height)
+          },
+          DT_SUCCESS
+        );
+        iter_position[1] = height;
+      }
+
+      smooth_path.push(iter_position);
+    }
+
+    let (connection_start, connection_end) = off_mesh_connection_end_points
+      .expect("The off-mesh connection should have been traversed.");
+    assert!(distance(&connection_start, &[0.9, 0.0, 0.5]) < 0.001);
+    assert!(distance(&connection_end, &[2.1, 0.5, 0.5]) < 0.001);
+
+    let last_point = smooth_path[smooth_path.len() - 1];
+    assert!(
+      distance(&last_point, &end_point) < 0.01,
+      "\n\nleft: {:?}\nright: {:?}",
+      last_point,
+      end_point
+    );
+
+    for point in smooth_path.iter() {
+      let expected_height = if point[0] < 1.5 { 0.0 } else { 0.5 };
+      assert!((point[1] - expected_height).abs() < 0.001, "point: {:?}", point);
+    }
+
+    // Apart from the off-mesh connection, points are at most a step apart.
+    for pair in smooth_path.windows(2) {
+      if pair[0][0] < 1.5 && pair[1][0] >= 1.5 {
+        continue;
+      }
+      assert!(
+        distance(&pair[0], &pair[1]) <= STEP_SIZE + 0.001,
+        "pair: {:?}",
+        pair
+      );
+    }
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_finds_random_points_with_forwarded_random() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    // A tiny linear congruential generator, so the test controls the seed.
+    struct Lcg(u32);
+
+    extern "C" fn next_random(object_ptr: *mut std::ffi::c_void) -> f32 {
+      let lcg = unsafe { &mut *(object_ptr as *mut Lcg) };
+      lcg.0 = lcg.0.wrapping_mul(1664525).wrapping_add(1013904223);
+      (lcg.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    let find_random_point = |seed: u32| {
+      let mut lcg = Lcg(seed);
+      let mut random_ref = 0;
+      let mut random_point = [0.0; 3];
+      assert_eq!(
+        unsafe {
+          FindRandomPointForwarded(
+            &*query,
+            &query_filter,
+            &mut lcg as *mut Lcg as *mut std::ffi::c_void,
+            Some(next_random),
+            &mut random_ref,
+            random_point.as_mut_ptr(),
+          )
+        },
+        DT_SUCCESS
+      );
+      assert_ne!(random_ref, 0);
+      (random_ref, random_point)
+    };
+
+    let center_point = [1.5, 0.0, 1.5];
+    let center_ref = find_nearest_poly(query, &center_point);
+
+    let find_random_point_around_circle = |seed: u32| {
+      let mut lcg = Lcg(seed);
+      let mut random_ref = 0;
+      let mut random_point = [0.0; 3];
+      assert_eq!(
+        unsafe {
+          FindRandomPointAroundCircleForwarded(
+            &*query,
+            center_ref,
+            center_point.as_ptr(),
+            /*maxRadius=*/ 1.0,
+            &query_filter,
+            &mut lcg as *mut Lcg as *mut std::ffi::c_void,
+            Some(next_random),
+            &mut random_ref,
+            random_point.as_mut_ptr(),
+          )
+        },
+        DT_SUCCESS
+      );
+      assert_ne!(random_ref, 0);
+      (random_ref, random_point)
+    };
+
+    for seed in [1, 2, 1337] {
+      let (random_ref, random_point) = find_random_point(seed);
+      assert_eq!(find_random_point(seed), (random_ref, random_point));
+      assert!(
+        (0.0..=3.0).contains(&random_point[0])
+          && (0.0..=2.0).contains(&random_point[2]),
+        "random_point: {:?}",
+        random_point
+      );
+
+      let (random_ref, random_point) = find_random_point_around_circle(seed);
+      assert_eq!(
+        find_random_point_around_circle(seed),
+        (random_ref, random_point)
+      );
+    }
+
+    assert_ne!(find_random_point(1).1, find_random_point(2).1);
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_raycasts() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    let raycast =
+      |start_point: &[f32; 3], end_point: &[f32; 3], options: u32| {
+        let mut path = [0; 10];
+        let mut hit = dtRaycastHit {
+          t: 0.0,
+          hitNormal: [0.0; 3],
+          hitEdgeIndex: 0,
+          path: path.as_mut_ptr(),
+          pathCount: 0,
+          maxPath: path.len() as i32,
+          pathCost: 0.0,
+        };
+        assert_eq!(
+          unsafe {
+            query.raycast1(
+              find_nearest_poly(query, start_point),
+              start_point.as_ptr(),
+              end_point.as_ptr(),
+              &query_filter,
+              options,
+              &mut hit,
+              /*prevRef=*/ 0,
+            )
+          },
+          DT_SUCCESS
+        );
+        let path = path[..hit.pathCount as usize].to_vec();
+        (hit, path)
+      };
+
+    // Both points are in the same square, so the ray reaches the end.
+    let start_point = [1.1, 0.0, 0.1];
+    let end_point = [1.9, 0.0, 0.9];
+    let (hit, path) = raycast(&start_point, &end_point, 0);
+    assert_eq!(hit.t, f32::MAX);
+    assert_eq!(
+      path.iter().map(|polyref| polyref & 0b111).collect::<Vec<_>>(),
+      [0, 1]
+    );
+    assert_eq!(hit.pathCost, 0.0);
+
+    // Costs are only accumulated when requested.
+    let (hit, _) =
+      raycast(&start_point, &end_point, dtRaycastOptions_DT_RAYCAST_USE_COSTS);
+    assert_eq!(hit.t, f32::MAX);
+    assert!(hit.pathCost > 0.0);
+
+    // Aim through the wall at z = 0. The wall is a third of the way along the
+    // ray.
+    let start_point = [1.4, 0.0, 0.4];
+    let end_point = [1.4, 0.0, -0.8];
+    let (hit, path) = raycast(&start_point, &end_point, 0);
+    assert!((hit.t - 1.0 / 3.0).abs() < 0.001, "t: {}", hit.t);
+    assert!(hit.hitNormal[0].abs() < 0.001, "normal: {:?}", hit.hitNormal);
+    assert!(
+      (hit.hitNormal[2].abs() - 1.0).abs() < 0.001,
+      "normal: {:?}",
+      hit.hitNormal
+    );
+    assert!((0..3).contains(&hit.hitEdgeIndex));
+    assert_eq!(
+      path.iter().map(|polyref| polyref & 0b111).collect::<Vec<_>>(),
+      [0]
+    );
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_spatial_queries() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+
+    let query_filter = create_query_filter();
+
+    let center_point = [1.3, 0.0, 0.4];
+    let center_ref = find_nearest_poly(query, &center_point);
+    assert_eq!(center_ref & 0b111, 0);
+
+    fn poly_indices(poly_refs: &[dtPolyRef]) -> Vec<dtPolyRef> {
+      let mut indices =
+        poly_refs.iter().map(|polyref| polyref & 0b111).collect::<Vec<_>>();
+      indices.sort();
+      indices
+    }
+
+    const MAX_RESULTS: usize = 10;
+
+    // Polys touched by a circle.
+    let find_polys_around_circle = |radius: f32| {
+      let mut result_refs = [0; MAX_RESULTS];
+      let mut result_parents = [0; MAX_RESULTS];
+      let mut result_costs = [0.0; MAX_RESULTS];
+      let mut result_count = 0;
+      assert_eq!(
+        unsafe {
+          query.findPolysAroundCircle(
+            center_ref,
+            center_point.as_ptr(),
+            radius,
+            &query_filter,
+            result_refs.as_mut_ptr(),
+            result_parents.as_mut_ptr(),
+            result_costs.as_mut_ptr(),
+            &mut result_count,
+            MAX_RESULTS as i32,
+          )
+        },
+        DT_SUCCESS
+      );
+      let result_count = result_count as usize;
+      (
+        result_refs[..result_count].to_vec(),
+        result_parents[..result_count].to_vec(),
+        result_costs[..result_count].to_vec(),
+      )
+    };
+
+    let (result_refs, result_parents, result_costs) =
+      find_polys_around_circle(0.3);
+    assert_eq!(poly_indices(&result_refs), [0, 1]);
+    assert_eq!(result_refs[0], center_ref);
+    assert_eq!(result_parents[0], 0);
+    assert_eq!(result_costs[0], 0.0);
+    assert_eq!(result_parents[1], center_ref);
+    assert!(result_costs[1] > 0.0);
+
+    let (result_refs, _, _) = find_polys_around_circle(10.0);
+    assert_eq!(poly_indices(&result_refs), [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // Polys touched by a convex shape covering most of the first square.
+    let shape = [
+      1.05, 0.0, 0.95, //
+      1.05, 0.0, 0.05, //
+      1.95, 0.0, 0.05, //
+      1.95, 0.0, 0.95, //
+    ];
+    let mut result_refs = [0; MAX_RESULTS];
+    let mut result_count = 0;
+    assert_eq!(
+      unsafe {
+        query.findPolysAroundShape(
+          center_ref,
+          shape.as_ptr(),
+          shape.len() as i32 / 3,
+          &query_filter,
+          result_refs.as_mut_ptr(),
+          std::ptr::null_mut(),
+          std::ptr::null_mut(),
+          &mut result_count,
+          MAX_RESULTS as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+    assert_eq!(poly_indices(&result_refs[..result_count as usize]), [0, 1]);
+
+    // Non-overlapping polys near the center.
+    let mut result_refs = [0; MAX_RESULTS];
+    let mut result_parents = [0; MAX_RESULTS];
+    let mut result_count = 0;
+    assert_eq!(
+      unsafe {
+        query.findLocalNeighbourhood(
+          center_ref,
+          center_point.as_ptr(),
+          /*radius=*/ 0.3,
+          &query_filter,
+          result_refs.as_mut_ptr(),
+          result_parents.as_mut_ptr(),
+          &mut result_count,
+          MAX_RESULTS as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+    assert_eq!(poly_indices(&result_refs[..result_count as usize]), [0, 1]);
+    assert_eq!(result_parents[0], 0);
+
+    // The first poly has two walls and one portal.
+    const MAX_SEGMENTS: usize = 6;
+    let mut segment_verts = [0.0; MAX_SEGMENTS * 6];
+    let mut segment_refs = [0; MAX_SEGMENTS];
+    let mut segment_count = 0;
+    assert_eq!(
+      unsafe {
+        query.getPolyWallSegments(
+          center_ref,
+          &query_filter,
+          segment_verts.as_mut_ptr(),
+          std::ptr::null_mut(),
+          &mut segment_count,
+          MAX_SEGMENTS as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+    assert_eq!(segment_count, 2);
+
+    assert_eq!(
+      unsafe {
+        query.getPolyWallSegments(
+          center_ref,
+          &query_filter,
+          segment_verts.as_mut_ptr(),
+          segment_refs.as_mut_ptr(),
+          &mut segment_count,
+          MAX_SEGMENTS as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+    assert_eq!(segment_count, 3);
+    let mut segment_neighbours = segment_refs[..segment_count as usize]
+      .iter()
+      .map(|polyref| if *polyref == 0 { None } else { Some(polyref & 0b111) })
+      .collect::<Vec<_>>();
+    segment_neighbours.sort();
+    assert_eq!(segment_neighbours, [None, None, Some(1)]);
+
+    // The closest wall is the one at x = 1.
+    let mut hit_distance = 0.0;
+    let mut hit_position = [0.0; 3];
+    let mut hit_normal = [0.0; 3];
+    assert_eq!(
+      unsafe {
+        query.findDistanceToWall(
+          center_ref,
+          center_point.as_ptr(),
+          /*maxRadius=*/ 5.0,
+          &query_filter,
+          &mut hit_distance,
+          hit_position.as_mut_ptr(),
+          hit_normal.as_mut_ptr(),
+        )
+      },
+      DT_SUCCESS
+    );
+    assert!((hit_distance - 0.3).abs() < 0.001, "distance: {}", hit_distance);
+    assert!(
+      distance(&hit_position, &[1.0, 0.0, 0.4]) < 0.001,
+      "position: {:?}",
+      hit_position
+    );
+    assert!(
+      distance(&hit_normal, &[1.0, 0.0, 0.0]) < 0.001,
+      "normal: {:?}",
+      hit_normal
+    );
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_queries_shared_nav_mesh_from_multiple_threads() {
+    // The bindings do not mark dtNavMesh as Send or Sync, since that is only
+    // sound while nothing mutates it concurrently. Here, reads and writes are
+    // serialized through an RwLock, so the test opts in locally.
+    struct SharedNavMesh(*mut dtNavMesh);
+    unsafe impl Send for SharedNavMesh {}
+    unsafe impl Sync for SharedNavMesh {}
+
+    impl SharedNavMesh {
+      fn get(&self) -> *mut dtNavMesh {
+        self.0
+      }
+    }
+
+    let nav_mesh = create_simple_nav_mesh();
+    let shared_nav_mesh = std::sync::RwLock::new(SharedNavMesh(nav_mesh));
+
+    // A poly off the path between the start and end points.
+    let toggled_poly_ref = {
+      let query = unsafe { &mut *dtAllocNavMeshQuery() };
+      assert_eq!(unsafe { query.init(nav_mesh, 512) }, DT_SUCCESS);
+      let poly_ref = find_nearest_poly(query, &[0.2, 0.0, 1.5]);
+      unsafe { dtFreeNavMeshQuery(query) };
+      poly_ref
+    };
+    assert_eq!(toggled_poly_ref & 0b111, 7);
+
+    std::thread::scope(|scope| {
+      for _ in 0..8 {
+        scope.spawn(|| {
+          // Each thread needs its own query, since queries hold search state.
+          let query = unsafe { &mut *dtAllocNavMeshQuery() };
+          assert_eq!(
+            unsafe { query.init(shared_nav_mesh.read().unwrap().get(), 512) },
+            DT_SUCCESS
+          );
+
+          for _ in 0..1000 {
+            let _guard = shared_nav_mesh.read().unwrap();
+            let path = find_path(query, &[1.1, 0.0, 0.1], &[2.9, 0.0, 1.9]);
+            assert_eq!(
+              path.iter().map(|polyref| polyref & 0b111).collect::<Vec<_>>(),
+              [0, 1, 2, 4, 5]
+            );
+          }
+
+          unsafe { dtFreeNavMeshQuery(query) };
+        });
+      }
+
+      scope.spawn(|| {
+        for i in 0..1000 {
+          let guard = shared_nav_mesh.write().unwrap();
+          assert_eq!(
+            unsafe {
+              (*guard.get()).setPolyFlags(toggled_poly_ref, 1 + (i % 2) as u16)
+            },
+            DT_SUCCESS
+          );
+        }
+      });
+    });
+
+    let mut flags = 0;
+    assert_eq!(
+      unsafe { (*nav_mesh).getPolyFlags(toggled_poly_ref, &mut flags) },
+      DT_SUCCESS
+    );
+    assert_eq!(flags, 2);
+
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour_crowd")]
+  #[test]
+  fn detour_crowd_survives_tile_swap() {
+    let nav_mesh = unsafe { &mut *create_simple_nav_mesh() };
+
+    let crowd = unsafe { &mut *dtAllocCrowd() };
+    assert!(unsafe { crowd.init(10, 10.0, nav_mesh) });
+
+    let agent_position = [1.1, 0.0, 0.1];
+    let agent_params = dtCrowdAgentParams {
+      radius: 0.25,
+      height: 1.0,
+      maxAcceleration: 0.5,
+      maxSpeed: 1.0,
+      collisionQueryRange: 1.0,
+      pathOptimizationRange: 10.0,
+      separationWeight: 1.0,
+      updateFlags: 0,
+      obstacleAvoidanceType: 0,
+      queryFilterType: 0,
+      userData: std::ptr::null_mut(),
+    };
+    assert_eq!(
+      unsafe { crowd.addAgent(agent_position.as_ptr(), &agent_params) },
+      0
+    );
+
+    let target_point = [2.9, 0.0, 1.9];
+    let target_poly_ref =
+      find_nearest_poly(unsafe { &*crowd.m_navquery }, &target_point);
+    assert!(unsafe {
+      crowd.requestMoveTarget(0, target_poly_ref, target_point.as_ptr())
+    });
+
+    for _ in 0..10 {
+      unsafe { crowd.update(0.1, std::ptr::null_mut()) };
+    }
+
+    // Collects every poly ref in a tile.
+    fn poly_refs_of_tile(
+      nav_mesh: &dtNavMesh,
+      tile_ref: dtTileRef,
+    ) -> Vec<dtPolyRef> {
+      let tile = unsafe { nav_mesh.getTileByRef(tile_ref) };
+      assert_ne!(tile, std::ptr::null());
+      let base = unsafe { nav_mesh.getPolyRefBase(tile) };
+      let poly_count = unsafe { (*(*tile).header).polyCount };
+      (0..poly_count as dtPolyRef).map(|i| base | i).collect()
+    }
+
+    let old_tile_ref = unsafe { nav_mesh.getTileRefAt(0, 0, 0) };
+    let old_poly_refs = poly_refs_of_tile(nav_mesh, old_tile_ref);
+    assert_eq!(old_poly_refs.len(), 8);
+
+    let agent_poly_ref =
+      unsafe { GetPathCorridorFirstPoly(&(*crowd.getAgent(0)).corridor) };
+    assert!(old_poly_refs.contains(&agent_poly_ref));
+
+    // Swap in a fresh copy of the tile. The tile was added with
+    // DT_TILE_FREE_DATA, so removing it frees the old data.
+    assert_eq!(
+      unsafe {
+        nav_mesh.removeTile(
+          old_tile_ref,
+          std::ptr::null_mut(),
+          std::ptr::null_mut(),
+        )
+      },
+      DT_SUCCESS
+    );
+
+    let (data, data_size) = create_simple_nav_mesh_data();
+    let mut new_tile_ref = 0;
+    assert_eq!(
+      unsafe {
+        nav_mesh.addTile(
+          data,
+          data_size,
+          dtTileFlags_DT_TILE_FREE_DATA as i32,
+          /*lastRef=*/ 0,
+          &mut new_tile_ref,
+        )
+      },
+      DT_SUCCESS
+    );
+    assert_ne!(new_tile_ref, old_tile_ref);
+
+    // The salt in the refs means every old ref is now detectably stale.
+    assert!(old_poly_refs
+      .iter()
+      .all(|poly_ref| !unsafe { nav_mesh.isValidPolyRef(*poly_ref) }));
+    let new_poly_refs = poly_refs_of_tile(nav_mesh, new_tile_ref);
+    assert!(new_poly_refs
+      .iter()
+      .all(|poly_ref| unsafe { nav_mesh.isValidPolyRef(*poly_ref) }));
+
+    // The crowd re-anchors the agent and its target onto the new tile.
+    for _ in 0..200 {
+      unsafe { crowd.update(0.1, std::ptr::null_mut()) };
+    }
+
+    let agent = unsafe { &*crowd.getAgent(0) };
+    assert!(new_poly_refs
+      .contains(&unsafe { GetPathCorridorFirstPoly(&agent.corridor) }));
+    assert!(
+      distance(&agent.npos, &target_point) < 0.01,
+      "\n\nleft: {:?}\nright: {:?}",
+      agent.npos,
+      target_point
+    );
+
+    unsafe { dtFreeCrowd(crowd) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_stores_and_restores_tile_state() {
+    let nav_mesh = unsafe { &mut *create_simple_nav_mesh() };
+
+    let tile = unsafe { nav_mesh.getTileAt(0, 0, 0) };
+    assert_ne!(tile, std::ptr::null());
+
+    let poly_ref = unsafe { nav_mesh.getPolyRefBase(tile) } | 2;
+
+    let get_flags_and_area = |nav_mesh: &dtNavMesh| {
+      let mut flags = 0;
+      let mut area = 0;
+      assert_eq!(
+        unsafe { nav_mesh.getPolyFlags(poly_ref, &mut flags) },
+        DT_SUCCESS
+      );
+      assert_eq!(
+        unsafe { nav_mesh.getPolyArea(poly_ref, &mut area) },
+        DT_SUCCESS
+      );
+      (flags, area)
+    };
+    assert_eq!(get_flags_and_area(nav_mesh), (1, 0));
+
+    let state_size = unsafe { nav_mesh.getTileStateSize(tile) };
+    assert!(state_size > 0);
+
+    let mut state = vec![0; state_size as usize];
+    assert_eq!(
+      unsafe {
+        nav_mesh.storeTileState(tile, state.as_mut_ptr(), state.len() as i32)
+      },
+      DT_SUCCESS
+    );
+
+    // Simulate a runtime change, like a door closing.
+    assert_eq!(unsafe { nav_mesh.setPolyFlags(poly_ref, 0) }, DT_SUCCESS);
+    assert_eq!(unsafe { nav_mesh.setPolyArea(poly_ref, 5) }, DT_SUCCESS);
+    assert_eq!(get_flags_and_area(nav_mesh), (0, 5));
+
+    // A buffer that is too small is rejected.
+    assert_ne!(
+      unsafe {
+        nav_mesh.restoreTileState(
+          tile as *mut dtMeshTile,
+          state.as_ptr(),
+          state.len() as i32 - 1,
+        )
+      } & DT_FAILURE,
+      0
+    );
+    assert_eq!(get_flags_and_area(nav_mesh), (0, 5));
+
+    assert_eq!(
+      unsafe {
+        nav_mesh.restoreTileState(
+          tile as *mut dtMeshTile,
+          state.as_ptr(),
+          state.len() as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+    assert_eq!(get_flags_and_area(nav_mesh), (1, 0));
+
+    // The state is tied to the tile ref, so it cannot be restored onto a
+    // replaced tile.
+    let tile_ref = unsafe { nav_mesh.getTileRef(tile) };
+    assert_eq!(
+      unsafe {
+        nav_mesh.removeTile(
+          tile_ref,
+          std::ptr::null_mut(),
+          std::ptr::null_mut(),
+        )
+      },
+      DT_SUCCESS
+    );
+    let (data, data_size) = create_simple_nav_mesh_data();
+    assert_eq!(
+      unsafe {
+        nav_mesh.addTile(
+          data,
+          data_size,
+          dtTileFlags_DT_TILE_FREE_DATA as i32,
+          /*lastRef=*/ 0,
+          std::ptr::null_mut(),
+        )
+      },
+      DT_SUCCESS
+    );
+
+    let tile = unsafe { nav_mesh.getTileAt(0, 0, 0) };
+    assert_ne!(
+      unsafe {
+        nav_mesh.restoreTileState(
+          tile as *mut dtMeshTile,
+          state.as_ptr(),
+          state.len() as i32,
+        )
+      } & DT_FAILURE,
+      0
+    );
+
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_edits_polys_in_box() {
+    let (nav_mesh, query) = create_simple_nav_mesh_and_query();
+    let nav_mesh = unsafe { &mut *nav_mesh };
+
+    let query_filter = create_query_filter();
+
+    // A box over the two polys in the top-left corner.
+    let box_center = [0.5, 0.0, 1.5];
+    let box_half_extents = [0.4, 1.0, 0.4];
+
+    extern "C" fn collect_poly_refs(
+      object_ptr: *mut std::ffi::c_void,
+      _tile: *const dtMeshTile,
+      _polys: *mut *mut dtPoly,
+      refs: *mut dtPolyRef,
+      count: i32,
+    ) {
+      let poly_refs = unsafe { &mut *(object_ptr as *mut Vec<dtPolyRef>) };
+      poly_refs.extend_from_slice(unsafe {
+        std::slice::from_raw_parts(refs, count as usize)
+      });
+    }
+
+    let mut poly_refs: Vec<dtPolyRef> = Vec::new();
+    let forwarded_poly_query = unsafe {
+      CreateForwardedPolyQuery(
+        &mut poly_refs as *mut Vec<dtPolyRef> as *mut std::ffi::c_void,
+        Some(collect_poly_refs),
+      )
+    };
+    assert_eq!(
+      unsafe {
+        query.queryPolygons1(
+          box_center.as_ptr(),
+          box_half_extents.as_ptr(),
+          &query_filter,
+          forwarded_poly_query,
+        )
+      },
+      DT_SUCCESS
+    );
+    unsafe { DeletePolyQuery(forwarded_poly_query) };
+
+    poly_refs.sort();
+    assert_eq!(
+      poly_refs.iter().map(|polyref| polyref & 0b111).collect::<Vec<_>>(),
+      [6, 7]
+    );
+
+    // The buffer-based overload finds the same polys.
+    let mut buffered_poly_refs = [0; 10];
+    let mut buffered_poly_count = 0;
+    assert_eq!(
+      unsafe {
+        query.queryPolygons(
+          box_center.as_ptr(),
+          box_half_extents.as_ptr(),
+          &query_filter,
+          buffered_poly_refs.as_mut_ptr(),
+          &mut buffered_poly_count,
+          buffered_poly_refs.len() as i32,
+        )
+      },
+      DT_SUCCESS
+    );
+    let mut buffered_poly_refs =
+      buffered_poly_refs[..buffered_poly_count as usize].to_vec();
+    buffered_poly_refs.sort();
+    assert_eq!(buffered_poly_refs, poly_refs);
+
+    // Disable the polys, keeping the previous flags to undo the edit.
+    let previous_flags = poly_refs
+      .iter()
+      .map(|poly_ref| {
+        let mut flags = 0;
+        assert_eq!(
+          unsafe { nav_mesh.getPolyFlags(*poly_ref, &mut flags) },
+          DT_SUCCESS
+        );
+        assert_eq!(unsafe { nav_mesh.setPolyFlags(*poly_ref, 0) }, DT_SUCCESS);
+        flags
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(previous_flags, [1, 1]);
+
+    // The filter now excludes the disabled polys.
+    let point_in_box = [0.2, 0.0, 1.5];
+    assert_eq!(find_nearest_poly(query, &point_in_box), 0);
+
+    for (poly_ref, flags) in poly_refs.iter().zip(previous_flags) {
+      assert_eq!(
+        unsafe { nav_mesh.setPolyFlags(*poly_ref, flags) },
+        DT_SUCCESS
+      );
+    }
+    assert_eq!(find_nearest_poly(query, &point_in_box) & 0b111, 7);
+
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "detour")]
+  #[test]
+  fn detour_inspects_tiles_and_polys() {
+    let nav_mesh = unsafe { &mut *create_simple_nav_mesh() };
+
+    let tiles = (0..unsafe { nav_mesh.getMaxTiles() })
+      .map(|i| unsafe { &*nav_mesh.getTile(i) })
+      .filter(|tile| !tile.header.is_null())
+      .collect::<Vec<_>>();
+    assert_eq!(tiles.len(), 1);
+
+    let tile = tiles[0];
+    let header = unsafe { &*tile.header };
+    assert_eq!(header.polyCount, 8);
+    assert_eq!(header.vertCount, 10);
+    assert_eq!(header.detailMeshCount, 8);
+
+    let polys = unsafe {
+      std::slice::from_raw_parts(tile.polys, header.polyCount as usize)
+    };
+    let verts = unsafe {
+      std::slice::from_raw_parts(tile.verts, header.vertCount as usize * 3)
+    };
+    let detail_meshes = unsafe {
+      std::slice::from_raw_parts(
+        tile.detailMeshes,
+        header.detailMeshCount as usize,
+      )
+    };
+
+    let poly_ref_base = unsafe { nav_mesh.getPolyRefBase(tile) };
+
+    let mut neighbour_link_count = 0;
+    for (poly_index, poly) in polys.iter().enumerate() {
+      let poly_ref = poly_ref_base | poly_index as dtPolyRef;
+
+      let (mut salt, mut tile_index, mut decoded_poly_index) = (0, 0, 0);
+      unsafe {
+        DecodePolyId(
+          nav_mesh,
+          poly_ref,
+          &mut salt,
+          &mut tile_index,
+          &mut decoded_poly_index,
+        )
+      };
+      assert_eq!(tile_index, 0);
+      assert_eq!(decoded_poly_index as usize, poly_index);
+      assert_eq!(salt, tile.salt);
+      assert_eq!(
+        unsafe { EncodePolyId(nav_mesh, salt, tile_index, decoded_poly_index) },
+        poly_ref
+      );
+
+      assert_eq!(unsafe { GetPolyArea(poly) }, 0);
+      assert_eq!(
+        unsafe { GetPolyType(poly) },
+        dtPolyTypes_DT_POLYTYPE_GROUND as u8
+      );
+      assert_eq!(poly.flags, 1);
+      assert_eq!(poly.vertCount, 3);
+
+      // All vertices are on the ground plane.
+      for vert_index in &poly.verts[..poly.vertCount as usize] {
+        let vert = &verts[*vert_index as usize * 3..][..3];
+        assert_eq!(vert[1], 0.0);
+      }
+
+      // Walk the linked list of links for this poly.
+      let mut link_index = poly.firstLink;
+      while link_index != DT_NULL_LINK {
+        let link = unsafe { &*tile.links.add(link_index as usize) };
+        assert_ne!(link.ref_, 0);
+        neighbour_link_count += 1;
+        link_index = link.next;
+      }
+
+      // Polys without detail meshes get a detail mesh of their triangulation.
+      assert_eq!(detail_meshes[poly_index].triCount, 1);
+    }
+
+    // 7 internal edges, each linked from both sides.
+    assert_eq!(neighbour_link_count, 14);
+
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "debug_utils")]
+  #[test]
+  fn debug_utils_forwards_debug_draw() {
+    let nav_mesh = unsafe { &mut *create_simple_nav_mesh() };
+
+    #[derive(Default)]
+    struct VertexCounter {
+      primitive: Option<duDebugDrawPrimitives>,
+      points: usize,
+      lines: usize,
+      tris: usize,
+      quads: usize,
+    }
+
+    extern "C" fn depth_mask(_object_ptr: *mut std::ffi::c_void, _state: bool) {
+    }
+
+    extern "C" fn texture(_object_ptr: *mut std::ffi::c_void, _state: bool) {}
+
+    extern "C" fn begin(
+      object_ptr: *mut std::ffi::c_void,
+      prim: duDebugDrawPrimitives,
+      _size: f32,
+    ) {
+      let counter = unsafe { &mut *(object_ptr as *mut VertexCounter) };
+      assert_eq!(counter.primitive, None);
+      counter.primitive = Some(prim);
+    }
+
+    extern "C" fn vertex(
+      object_ptr: *mut std::ffi::c_void,
+      _pos: *const f32,
+      _color: u32,
+      _uv: *const f32,
+    ) {
+      let counter = unsafe { &mut *(object_ptr as *mut VertexCounter) };
+      match counter.primitive.expect("vertex called outside begin/end") {
+        duDebugDrawPrimitives_DU_DRAW_POINTS => counter.points += 1,
+        duDebugDrawPrimitives_DU_DRAW_LINES => counter.lines += 1,
+        duDebugDrawPrimitives_DU_DRAW_TRIS => counter.tris += 1,
+        duDebugDrawPrimitives_DU_DRAW_QUADS => counter.quads += 1,
+        unknown => panic!("Unknown primitive: {}", unknown),
+      }
+    }
+
+    extern "C" fn end(object_ptr: *mut std::ffi::c_void) {
+      let counter = unsafe { &mut *(object_ptr as *mut VertexCounter) };
+      assert_ne!(counter.primitive, None);
+      counter.primitive = None;
+    }
+
+    let mut counter = VertexCounter::default();
+    let forwarded_debug_draw = unsafe {
+      CreateForwardedDebugDraw(
+        &mut counter as *mut VertexCounter as *mut std::ffi::c_void,
+        Some(depth_mask),
+        Some(texture),
+        Some(begin),
+        Some(vertex),
+        Some(end),
+      )
+    };
+
+    unsafe { duDebugDrawNavMesh(forwarded_debug_draw, nav_mesh, 0) };
+
+    unsafe { DeleteDebugDraw(forwarded_debug_draw) };
+
+    assert_eq!(counter.primitive, None);
+    // Each of the 8 polys is a single detail triangle.
+    assert_eq!(counter.tris, 8 * 3);
+    // One point per vertex.
+    assert_eq!(counter.points, 10);
+    assert!(counter.lines > 0);
+    assert_eq!(counter.quads, 0);
+
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  #[cfg(feature = "debug_utils")]
+  #[test]
+  fn debug_utils_captures_poly_debug_geometry() {
+    let nav_mesh = unsafe { &mut *create_simple_nav_mesh() };
+
+    // Records every batch of primitives, along with the vertex positions and
+    // colours.
+    #[derive(Default)]
+    struct DebugDrawCapture {
+      depth_mask: Vec<bool>,
+      batches: Vec<(duDebugDrawPrimitives, Vec<([f32; 3], u32)>)>,
+      in_batch: bool,
+    }
+
+    extern "C" fn depth_mask(object_ptr: *mut std::ffi::c_void, state: bool) {
+      let capture = unsafe { &mut *(object_ptr as *mut DebugDrawCapture) };
+      capture.depth_mask.push(state);
+    }
+
+    extern "C" fn texture(_object_ptr: *mut std::ffi::c_void, _state: bool) {}
+
+    extern "C" fn begin(
+      object_ptr: *mut std::ffi::c_void,
+      prim: duDebugDrawPrimitives,
+      _size: f32,
+    ) {
+      let capture = unsafe { &mut *(object_ptr as *mut DebugDrawCapture) };
+      assert!(!capture.in_batch);
+      capture.in_batch = true;
+      capture.batches.push((prim, Vec::new()));
+    }
+
+    extern "C" fn vertex(
+      object_ptr: *mut std::ffi::c_void,
+      pos: *const f32,
+      color: u32,
+      _uv: *const f32,
+    ) {
+      let capture = unsafe { &mut *(object_ptr as *mut DebugDrawCapture) };
+      assert!(capture.in_batch);
+      let pos = unsafe { std::slice::from_raw_parts(pos, 3) };
+      capture
+        .batches
+        .last_mut()
+        .unwrap()
+        .1
+        .push(([pos[0], pos[1], pos[2]], color));
+    }
+
+    extern "C" fn end(object_ptr: *mut std::ffi::c_void) {
+      let capture = unsafe { &mut *(object_ptr as *mut DebugDrawCapture) };
+      assert!(capture.in_batch);
+      capture.in_batch = false;
+    }
+
+    let capture_polys_with_flags = |nav_mesh: &dtNavMesh, flags: u16| {
+      let mut capture = DebugDrawCapture::default();
+      let forwarded_debug_draw = unsafe {
+        CreateForwardedDebugDraw(
+          &mut capture as *mut DebugDrawCapture as *mut std::ffi::c_void,
+          Some(depth_mask),
+          Some(texture),
+          Some(begin),
+          Some(vertex),
+          Some(end),
+        )
+      };
+      unsafe {
+        duDebugDrawNavMeshPolysWithFlags(
+          forwarded_debug_draw,
+          nav_mesh,
+          flags,
+          0x00ff8000,
+        )
+      };
+      unsafe { DeleteDebugDraw(forwarded_debug_draw) };
+      assert!(!capture.in_batch);
+      capture
+    };
+
+    let capture = capture_polys_with_flags(nav_mesh, 1);
+    // One triangle batch per poly.
+    assert_eq!(capture.batches.len(), 8);
+    for (primitive, vertices) in capture.batches.iter() {
+      assert_eq!(*primitive, duDebugDrawPrimitives_DU_DRAW_TRIS);
+      assert_eq!(vertices.len(), 3);
+      for (position, color) in vertices.iter() {
+        assert_eq!(position[1], 0.0);
+        // Polys are drawn with the requested colour, made translucent.
+        assert_eq!(*color, (64 << 24) | 0x00ff8000);
+      }
+    }
+    assert_eq!(capture.depth_mask, [false, true].repeat(8));
+
+    // Only polys with matching flags are drawn.
+    let query = unsafe { &mut *dtAllocNavMeshQuery() };
+    assert_eq!(unsafe { query.init(nav_mesh, 512) }, DT_SUCCESS);
+    let poly_ref = find_nearest_poly(query, &[0.2, 0.0, 1.5]);
+    unsafe { dtFreeNavMeshQuery(query) };
+    assert_eq!(unsafe { nav_mesh.setPolyFlags(poly_ref, 2) }, DT_SUCCESS);
+
+    assert_eq!(capture_polys_with_flags(nav_mesh, 1).batches.len(), 7);
+    let capture = capture_polys_with_flags(nav_mesh, 2);
+    assert_eq!(capture.batches.len(), 1);
+    let mut triangle = capture.batches[0]
+      .1
+      .iter()
+      .map(|(position, _)| *position)
+      .collect::<Vec<_>>();
+    triangle.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(triangle, [[0.0, 0.0, 1.0], [0.0, 0.0, 2.0], [1.0, 0.0, 1.0]]);
+
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
+
+  // Triangle geometry and the voxelization settings the recast tests build it
+  // with.
+  #[cfg(feature = "recast")]
+  struct RecastFixture {
+    verts: Vec<f32>,
+    triangles: Vec<i32>,
+    area_ids: Vec<u8>,
+    bmin: [f32; 3],
+    bmax: [f32; 3],
+    cs: f32,
+    ch: f32,
+    walkable_height: i32,
+    walkable_climb: i32,
+  }
+
+  #[cfg(feature = "recast")]
+  impl RecastFixture {
+    // Creates an empty fixture with 1x1x1 voxels, for an agent two voxels tall
+    // that can climb one voxel.
+    fn new(bmin: [f32; 3], bmax: [f32; 3]) -> Self {
+      Self {
+        verts: vec![],
+        triangles: vec![],
+        area_ids: vec![],
+        bmin,
+        bmax,
+        cs: 1.0,
+        ch: 1.0,
+        walkable_height: 2,
+        walkable_climb: 1,
+      }
+    }
+
+    // Adds a horizontal quad at height `y`.
+    fn add_quad(&mut self, min: [f32; 2], max: [f32; 2], y: f32, area_id: u8) {
+      let first = self.verts.len() as i32 / 3;
+      self.verts.extend_from_slice(&[
+        min[0], y, min[1], //
+        max[0], y, min[1], //
+        max[0], y, max[1], //
+        min[0], y, max[1], //
+      ]);
+      self
+        .triangles
+        .extend([0, 1, 2, 2, 3, 0].iter().map(|&index| first + index));
+      self.area_ids.extend_from_slice(&[area_id, area_id]);
+    }
+
+    fn rasterize(&self, context: *mut rcContext) -> *mut rcHeightfield {
+      let mut width = 0;
+      let mut height = 0;
+      unsafe {
+        rcCalcGridSize(
+          self.bmin.as_ptr(),
+          self.bmax.as_ptr(),
+          self.cs,
+          &mut width,
+          &mut height,
+        )
+      };
+
+      let heightfield = unsafe { rcAllocHeightfield() };
+      assert!(unsafe {
+        rcCreateHeightfield(
+          context,
+          heightfield,
+          width,
+          height,
+          self.bmin.as_ptr(),
+          self.bmax.as_ptr(),
+          self.cs,
+          self.ch,
+        )
+      });
+
+      assert!(unsafe {
+        rcRasterizeTriangles(
+          context,
+          self.verts.as_ptr(),
+          self.verts.len() as i32 / 3,
+          self.triangles.as_ptr(),
+          self.area_ids.as_ptr(),
+          self.area_ids.len() as i32,
+          heightfield,
+          self.walkable_climb,
+        )
+      });
+      heightfield
+    }
+
+    // Builds the compact heightfield and frees `heightfield`.
+    fn build_compact_heightfield(
+      &self,
+      context: *mut rcContext,
+      heightfield: *mut rcHeightfield,
+    ) -> *mut rcCompactHeightfield {
+      let compact_heightfield = unsafe { rcAllocCompactHeightfield() };
+      assert!(unsafe {
+        rcBuildCompactHeightfield(
+          context,
+          self.walkable_height,
+          self.walkable_climb,
+          heightfield,
+          compact_heightfield,
+        )
+      });
+      unsafe { rcFreeHeightField(heightfield) };
+      compact_heightfield
+    }
+  }
+
+  #[cfg(feature = "recast")]
+  fn build_contour_set(
+    context: *mut rcContext,
+    compact_heightfield: *mut rcCompactHeightfield,
+  ) -> *mut rcContourSet {
+    let contour_set = unsafe { rcAllocContourSet() };
+    assert!(unsafe {
+      rcBuildContours(
+        context,
+        compact_heightfield,
+        /*maxError=*/ 1.3,
+        /*maxEdgeLen=*/ 12,
+        contour_set,
+        rcBuildContoursFlags_RC_CONTOUR_TESS_WALL_EDGES as i32,
+      )
+    });
+    contour_set
+  }
+
+  #[cfg(feature = "recast")]
+  fn build_poly_mesh(
+    context: *mut rcContext,
+    contour_set: *mut rcContourSet,
+  ) -> *mut rcPolyMesh {
+    let mesh = unsafe { rcAllocPolyMesh() };
+    assert!(unsafe {
+      rcBuildPolyMesh(context, contour_set, /*nvp=*/ 6, mesh)
+    });
+    mesh
+  }
+
+  #[cfg(feature = "debug_utils")]
+  #[test]
+  fn debug_utils_dumps_recast_data() {
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [5.0, 5.0, 5.0]);
+    fixture.add_quad([0.0, 0.0], [5.0, 5.0], 0.5, RC_WALKABLE_AREA);
+
+    let context = unsafe { CreateContext(false) };
+    let heightfield = fixture.rasterize(context);
+    let compact_heightfield =
+      fixture.build_compact_heightfield(context, heightfield);
+
+    assert!(unsafe { rcErodeWalkableArea(context, 1, compact_heightfield) });
+    assert!(unsafe { rcBuildDistanceField(context, compact_heightfield) });
+    assert!(unsafe { rcBuildRegions(context, compact_heightfield, 0, 0, 0) });
+
+    let contour_set = build_contour_set(context, compact_heightfield);
+    let mesh = build_poly_mesh(context, contour_set);
+
+    // An in-memory file that can be written and then read back.
+    #[derive(Default)]
+    struct MemoryFile {
+      data: Vec<u8>,
+      read_offset: usize,
+      reading: bool,
+    }
+
+    extern "C" fn is_writing(object_ptr: *mut std::ffi::c_void) -> bool {
+      !unsafe { &*(object_ptr as *mut MemoryFile) }.reading
+    }
+
+    extern "C" fn is_reading(object_ptr: *mut std::ffi::c_void) -> bool {
+      unsafe { &*(object_ptr as *mut MemoryFile) }.reading
+    }
+
+    extern "C" fn write(
+      object_ptr: *mut std::ffi::c_void,
+      ptr: *const std::ffi::c_void,
+      size: usize,
+    ) -> bool {
+      let file = unsafe { &mut *(object_ptr as *mut MemoryFile) };
+      file.data.extend_from_slice(unsafe {
+        std::slice::from_raw_parts(ptr as *const u8, size)
+      });
+      true
+    }
+
+    extern "C" fn read(
+      object_ptr: *mut std::ffi::c_void,
+      ptr: *mut std::ffi::c_void,
+      size: usize,
+    ) -> bool {
+      let file = unsafe { &mut *(object_ptr as *mut MemoryFile) };
+      let Some(data) = file.data.get(file.read_offset..file.read_offset + size)
+      else {
+        return false;
+      };
+      unsafe { std::slice::from_raw_parts_mut(ptr as *mut u8, size) }
+        .copy_from_slice(data);
+      file.read_offset += size;
+      true
+    }
+
+    let create_file_io = |file: &mut MemoryFile| unsafe {
+      CreateForwardedFileIO(
+        file as *mut MemoryFile as *mut std::ffi::c_void,
+        Some(is_writing),
+        Some(is_reading),
+        Some(write),
+        Some(read),
+      )
+    };
+
+    // The poly mesh is dumped as a Wavefront OBJ.
+    let mut obj_file = MemoryFile::default();
+    let file_io = create_file_io(&mut obj_file);
+    assert!(unsafe { duDumpPolyMeshToObj(mesh, file_io) });
+    unsafe { DeleteFileIO(file_io) };
+
+    let obj = String::from_utf8(obj_file.data).unwrap();
+    assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 4);
+    // The single quad is split into two triangles.
+    assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 2);
+
+    // Contours and compact heightfields round-trip through the binary dumps.
+    let mut contour_file = MemoryFile::default();
+    let file_io = create_file_io(&mut contour_file);
+    assert!(unsafe { duDumpContourSet(contour_set, file_io) });
+    unsafe { DeleteFileIO(file_io) };
+
+    contour_file.reading = true;
+    let read_contour_set = unsafe { rcAllocContourSet() };
+    let file_io = create_file_io(&mut contour_file);
+    assert!(unsafe { duReadContourSet(read_contour_set, file_io) });
+    unsafe { DeleteFileIO(file_io) };
+    assert_eq!(contour_file.read_offset, contour_file.data.len());
+
+    let (contours, read_contours) = unsafe {
+      (
+        std::slice::from_raw_parts(
+          (*contour_set).conts,
+          (*contour_set).nconts as usize,
+        ),
+        std::slice::from_raw_parts(
+          (*read_contour_set).conts,
+          (*read_contour_set).nconts as usize,
+        ),
+      )
+    };
+    assert_eq!(read_contours.len(), contours.len());
+    for (contour, read_contour) in contours.iter().zip(read_contours) {
+      assert_eq!(read_contour.nverts, contour.nverts);
+      assert_eq!(read_contour.reg, contour.reg);
+      assert_eq!(
+        unsafe {
+          std::slice::from_raw_parts(
+            read_contour.verts,
+            read_contour.nverts as usize * 4,
+          )
+        },
+        unsafe {
+          std::slice::from_raw_parts(contour.verts, contour.nverts as usize * 4)
+        }
+      );
+    }
+
+    let mut heightfield_file = MemoryFile::default();
+    let file_io = create_file_io(&mut heightfield_file);
+    assert!(unsafe { duDumpCompactHeightfield(compact_heightfield, file_io) });
+    unsafe { DeleteFileIO(file_io) };
+
+    heightfield_file.reading = true;
+    let read_compact_heightfield = unsafe { rcAllocCompactHeightfield() };
+    let file_io = create_file_io(&mut heightfield_file);
+    assert!(unsafe {
+      duReadCompactHeightfield(read_compact_heightfield, file_io)
+    });
+    unsafe { DeleteFileIO(file_io) };
+
+    let (original, read) =
+      unsafe { (&*compact_heightfield, &*read_compact_heightfield) };
+    assert_eq!(read.spanCount, original.spanCount);
+    assert_eq!(read.maxRegions, original.maxRegions);
+    assert_eq!(
+      unsafe {
+        std::slice::from_raw_parts(read.areas, read.spanCount as usize)
+      },
+      unsafe {
+        std::slice::from_raw_parts(original.areas, original.spanCount as usize)
+      }
+    );
+
+    unsafe { rcFreeCompactHeightfield(read_compact_heightfield) };
+    unsafe { rcFreeContourSet(read_contour_set) };
+    unsafe { rcFreePolyMesh(mesh) };
+    unsafe { rcFreeContourSet(contour_set) };
+    unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    unsafe { DeleteContext(context) };
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_forwards_context_logs_and_timers() {
+    const MAX_TIMERS: usize = rcTimerLabel_RC_MAX_TIMERS as usize;
+
+    struct ContextState {
+      logs: Vec<(rcLogCategory, String)>,
+      started: [Option<std::time::Instant>; MAX_TIMERS],
+      accumulated: [Option<std::time::Duration>; MAX_TIMERS],
+    }
+
+    extern "C" fn reset_log(object_ptr: *mut std::ffi::c_void) {
+      unsafe { &mut *(object_ptr as *mut ContextState) }.logs.clear();
+    }
+
+    extern "C" fn log(
+      object_ptr: *mut std::ffi::c_void,
+      category: rcLogCategory,
+      msg: *const std::ffi::c_char,
+      len: i32,
+    ) {
+      let state = unsafe { &mut *(object_ptr as *mut ContextState) };
+      let msg =
+        unsafe { std::slice::from_raw_parts(msg as *const u8, len as usize) };
+      state.logs.push((category, String::from_utf8_lossy(msg).into_owned()));
+    }
+
+    extern "C" fn reset_timers(object_ptr: *mut std::ffi::c_void) {
+      let state = unsafe { &mut *(object_ptr as *mut ContextState) };
+      state.started = [None; MAX_TIMERS];
+      state.accumulated = [None; MAX_TIMERS];
+    }
+
+    extern "C" fn start_timer(
+      object_ptr: *mut std::ffi::c_void,
+      label: rcTimerLabel,
+    ) {
+      let state = unsafe { &mut *(object_ptr as *mut ContextState) };
+      state.started[label as usize] = Some(std::time::Instant::now());
+    }
+
+    extern "C" fn stop_timer(
+      object_ptr: *mut std::ffi::c_void,
+      label: rcTimerLabel,
+    ) {
+      let state = unsafe { &mut *(object_ptr as *mut ContextState) };
+      let Some(started) = state.started[label as usize].take() else {
+        return;
+      };
+      let accumulated =
+        state.accumulated[label as usize].get_or_insert_with(Default::default);
+      *accumulated += started.elapsed();
+    }
+
+    extern "C" fn get_accumulated_time(
+      object_ptr: *mut std::ffi::c_void,
+      label: rcTimerLabel,
+    ) -> i32 {
+      let state = unsafe { &*(object_ptr as *mut ContextState) };
+      state.accumulated[label as usize]
+        .map_or(-1, |accumulated| accumulated.as_micros() as i32)
+    }
+
+    let mut state = ContextState {
+      logs: vec![],
+      started: [None; MAX_TIMERS],
+      accumulated: [None; MAX_TIMERS],
+    };
+    let context = unsafe {
+      CreateForwardedContext(
+        &mut state as *mut ContextState as *mut std::ffi::c_void,
+        true,
+        Some(reset_log),
+        Some(log),
+        Some(reset_timers),
+        Some(start_timer),
+        Some(stop_timer),
+        Some(get_accumulated_time),
+      )
+    };
+
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [5.0, 5.0, 5.0]);
+    fixture.add_quad([0.0, 0.0], [5.0, 5.0], 0.5, RC_WALKABLE_AREA);
+
+    let heightfield = fixture.rasterize(context);
+    let compact_heightfield =
+      fixture.build_compact_heightfield(context, heightfield);
+
+    // Every timer that was started has been stopped again.
+    assert!(state.started.iter().all(Option::is_none));
+    assert!(state.accumulated
+      [rcTimerLabel_RC_TIMER_RASTERIZE_TRIANGLES as usize]
+      .is_some());
+    assert!(state.accumulated
+      [rcTimerLabel_RC_TIMER_BUILD_COMPACTHEIGHTFIELD as usize]
+      .is_some());
+    assert!(state.accumulated[rcTimerLabel_RC_TIMER_BUILD_POLYMESH as usize]
+      .is_none());
+
+    #[cfg(feature = "debug_utils")]
+    {
+      let total_time = state
+        .accumulated
+        .iter()
+        .flatten()
+        .sum::<std::time::Duration>()
+        .as_micros() as i32;
+      // Avoid a division by zero when the build was too fast to measure.
+      unsafe { duLogBuildTimes(context, total_time.max(1)) };
+
+      assert_eq!(
+        state.logs[0],
+        (rcLogCategory_RC_LOG_PROGRESS, "Build Times".into())
+      );
+      assert!(state
+        .logs
+        .iter()
+        .any(|(_, msg)| msg.starts_with("- Rasterize:")));
+      // Timers that never ran are skipped.
+      assert!(!state
+        .logs
+        .iter()
+        .any(|(_, msg)| msg.starts_with("- Build Polymesh:")));
+      assert!(state
+        .logs
+        .iter()
+        .all(|(category, _)| *category == rcLogCategory_RC_LOG_PROGRESS));
+    }
+
+    unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    unsafe { DeleteContext(context) };
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_calculates_config_in_voxel_units() {
+    // A 10x8 floor.
+    let mut floor = RecastFixture::new([0.0; 3], [0.0; 3]);
+    floor.add_quad([-1.0, -2.0], [9.0, 6.0], 0.5, RC_WALKABLE_AREA);
+
+    let (cs, ch) = (0.5, 0.25);
+
+    let mut bmin = [0.0; 3];
+    let mut bmax = [0.0; 3];
+    unsafe {
+      rcCalcBounds(
+        floor.verts.as_ptr(),
+        floor.verts.len() as i32 / 3,
+        bmin.as_mut_ptr(),
+        bmax.as_mut_ptr(),
+      )
+    };
+    assert_eq!(bmin, [-1.0, 0.5, -2.0]);
+    assert_eq!(bmax, [9.0, 0.5, 6.0]);
+
+    let mut width = 0;
+    let mut height = 0;
+    unsafe {
+      rcCalcGridSize(bmin.as_ptr(), bmax.as_ptr(), cs, &mut width, &mut height)
+    };
+    assert_eq!((width, height), (20, 16));
+
+    // Agent dimensions are given to Recast in voxels. Heights and radii round
+    // up so the agent always fits, climbs round down so it never climbs too
+    // high.
+    let (agent_height, agent_radius, agent_max_climb) =
+      (2.0f32, 0.6f32, 0.9f32);
+    let walkable_height = (agent_height / ch).ceil() as i32;
+    let walkable_radius = (agent_radius / cs).ceil() as i32;
+    let walkable_climb = (agent_max_climb / ch).floor() as i32;
+    assert_eq!(walkable_height, 8);
+    assert_eq!(walkable_radius, 2);
+    assert_eq!(walkable_climb, 3);
+    // Tiled builds need a border wide enough for erosion and filtering.
+    let border_size = walkable_radius + 3;
+    assert_eq!(border_size, 5);
+
+    let border = border_size as f32 * cs;
+    let fixture = RecastFixture {
+      bmin: [bmin[0] - border, bmin[1], bmin[2] - border],
+      // Leave room above the floor for the agent.
+      bmax: [bmax[0] + border, bmax[1] + agent_height, bmax[2] + border],
+      cs,
+      ch,
+      walkable_height,
+      walkable_climb,
+      ..floor
+    };
+
+    let context = unsafe { CreateContext(false) };
+    let heightfield = fixture.rasterize(context);
+    // The grid of the bounds grown by the border matches the grid size grown
+    // by the border.
+    assert_eq!(
+      unsafe { ((*heightfield).width, (*heightfield).height) },
+      (width + border_size * 2, height + border_size * 2)
+    );
+
+    let compact_heightfield =
+      fixture.build_compact_heightfield(context, heightfield);
+    assert_eq!(unsafe { (*compact_heightfield).spanCount }, 20 * 16);
+
+    // Eroding by the agent radius removes two voxels (1m) from every side of
+    // the floor, enough to keep the 0.6m agent off the edges.
+    assert!(unsafe {
+      rcErodeWalkableArea(context, walkable_radius, compact_heightfield)
+    });
+    let areas = unsafe {
+      std::slice::from_raw_parts(
+        (*compact_heightfield).areas,
+        (*compact_heightfield).spanCount as usize,
+      )
+    };
+    assert_eq!(
+      areas.iter().filter(|&&area| area != RC_NULL_AREA).count(),
+      16 * 12
+    );
+
+    unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    unsafe { DeleteContext(context) };
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_marks_areas() {
+    const WATER_AREA: u8 = 2;
+    const GRASS_AREA: u8 = 3;
+    const ROAD_AREA: u8 = 4;
+
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [20.0, 5.0, 20.0]);
+    fixture.add_quad([0.0, 0.0], [20.0, 20.0], 0.5, RC_WALKABLE_AREA);
+
+    let context = unsafe { CreateContext(false) };
+    let heightfield = fixture.rasterize(context);
+    let compact_heightfield =
+      fixture.build_compact_heightfield(context, heightfield);
+
+    assert!(unsafe { rcErodeWalkableArea(context, 1, compact_heightfield) });
+
+    // Areas must be marked after eroding (so the marked areas are not eroded
+    // away) and before building the distance field and regions (so regions
+    // follow the area boundaries).
+    unsafe {
+      rcMarkBoxArea(
+        context,
+        [0.0, -1.0, 0.0].as_ptr(),
+        [6.0, 5.0, 20.0].as_ptr(),
+        WATER_AREA,
+        compact_heightfield,
+      )
+    };
+    unsafe {
+      rcMarkCylinderArea(
+        context,
+        [14.0, -1.0, 14.0].as_ptr(),
+        /*r=*/ 3.0,
+        /*h=*/ 6.0,
+        GRASS_AREA,
+        compact_heightfield,
+      )
+    };
+
+    let road = [
+      12.0, 0.0, 2.0, //
+      12.0, 0.0, 7.0, //
+      17.0, 0.0, 7.0, //
+      17.0, 0.0, 2.0, //
+    ];
+    let mut offset_road = [0.0; 3 * 32];
+    let offset_road_verts = unsafe {
+      rcOffsetPoly(
+        road.as_ptr(),
+        road.len() as i32 / 3,
+        /*offset=*/ 0.5,
+        offset_road.as_mut_ptr(),
+        offset_road.len() as i32 / 3,
+      )
+    };
+    // Sharp corners may be beveled, adding vertices.
+    assert!(offset_road_verts >= 4);
+    unsafe {
+      rcMarkConvexPolyArea(
+        context,
+        offset_road.as_ptr(),
+        offset_road_verts,
+        /*hmin=*/ -1.0,
+        /*hmax=*/ 5.0,
+        ROAD_AREA,
+        compact_heightfield,
+      )
+    };
+
+    assert!(unsafe { rcBuildDistanceField(context, compact_heightfield) });
+    assert!(unsafe { rcBuildRegions(context, compact_heightfield, 0, 0, 0) });
+
+    let contour_set = build_contour_set(context, compact_heightfield);
+    let mesh = build_poly_mesh(context, contour_set);
+
+    let areas = unsafe {
+      std::slice::from_raw_parts((*mesh).areas, (*mesh).npolys as usize)
+    };
+    for area in [RC_WALKABLE_AREA, WATER_AREA, GRASS_AREA, ROAD_AREA] {
+      assert!(areas.contains(&area), "Missing area {area} in {areas:?}");
+    }
+
+    unsafe { rcFreePolyMesh(mesh) };
+    unsafe { rcFreeContourSet(contour_set) };
+    unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    unsafe { DeleteContext(context) };
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_partitions_regions() {
+    // A 12x12 floor with a 4x4 hole in the middle, made of four quads.
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [12.0, 5.0, 12.0]);
+    fixture.add_quad([0.0, 0.0], [12.0, 4.0], 0.5, RC_WALKABLE_AREA);
+    fixture.add_quad([0.0, 8.0], [12.0, 12.0], 0.5, RC_WALKABLE_AREA);
+    fixture.add_quad([0.0, 4.0], [4.0, 8.0], 0.5, RC_WALKABLE_AREA);
+    fixture.add_quad([8.0, 4.0], [12.0, 8.0], 0.5, RC_WALKABLE_AREA);
+
+    fn watershed(
+      context: *mut rcContext,
+      compact_heightfield: *mut rcCompactHeightfield,
+    ) -> bool {
+      unsafe {
+        rcBuildDistanceField(context, compact_heightfield)
+          && rcBuildRegions(context, compact_heightfield, 0, 0, 0)
+      }
+    }
+
+    fn monotone(
+      context: *mut rcContext,
+      compact_heightfield: *mut rcCompactHeightfield,
+    ) -> bool {
+      unsafe { rcBuildRegionsMonotone(context, compact_heightfield, 0, 0, 0) }
+    }
+
+    fn layers(
+      context: *mut rcContext,
+      compact_heightfield: *mut rcCompactHeightfield,
+    ) -> bool {
+      unsafe { rcBuildLayerRegions(context, compact_heightfield, 0, 0) }
+    }
+
+    let partitions: [(&str, fn(_, _) -> bool); 3] =
+      [("watershed", watershed), ("monotone", monotone), ("layers", layers)];
+
+    let context = unsafe { CreateContext(false) };
+    for (name, build_regions) in partitions {
+      let heightfield = fixture.rasterize(context);
+      let compact_heightfield =
+        fixture.build_compact_heightfield(context, heightfield);
+
+      assert!(unsafe { rcErodeWalkableArea(context, 1, compact_heightfield) });
+      assert!(build_regions(context, compact_heightfield), "{name}");
+
+      // Only watershed partitioning needs the distance field.
+      assert_eq!(
+        unsafe { (*compact_heightfield).dist.is_null() },
+        name != "watershed",
+        "{name}"
+      );
+      assert!(unsafe { (*compact_heightfield).maxRegions } > 0, "{name}");
+
+      let contour_set = build_contour_set(context, compact_heightfield);
+      let mesh = build_poly_mesh(context, contour_set);
+      let mesh_ref = unsafe { &*mesh };
+      assert!(mesh_ref.npolys > 0, "{name}");
+
+      let nvp = mesh_ref.nvp as usize;
+      let polys = unsafe {
+        std::slice::from_raw_parts(
+          mesh_ref.polys,
+          mesh_ref.npolys as usize * nvp * 2,
+        )
+      };
+      let regions = unsafe {
+        std::slice::from_raw_parts(mesh_ref.regs, mesh_ref.npolys as usize)
+      };
+      for (poly, &region) in polys.chunks(nvp * 2).zip(regions) {
+        let poly_verts: Vec<_> = poly[..nvp]
+          .iter()
+          .take_while(|&&vert| vert != RC_MESH_NULL_IDX)
+          .collect();
+        assert!(poly_verts.len() >= 3, "{name}: {poly:?}");
+        assert!(
+          poly_verts.iter().all(|&&vert| (vert as i32) < mesh_ref.nverts),
+          "{name}: {poly:?}"
+        );
+        assert_ne!(region, 0, "{name}");
+      }
+
+      // No vertex lies inside the hole.
+      let mesh_verts = unsafe {
+        std::slice::from_raw_parts(mesh_ref.verts, mesh_ref.nverts as usize * 3)
+      };
+      assert!(
+        mesh_verts.chunks(3).all(|vert| !((5..8).contains(&vert[0])
+          && (5..8).contains(&vert[2]))),
+        "{name}: {mesh_verts:?}"
+      );
+
+      unsafe { rcFreePolyMesh(mesh) };
+      unsafe { rcFreeContourSet(contour_set) };
+      unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    }
+    unsafe { DeleteContext(context) };
+  }
+
+  // Builds a poly mesh of `fixture`, applying `filter` to the
+  // heightfield after rasterization. Returns the number of compact spans and
+  // the mesh vertices in voxel coordinates.
+  #[cfg(feature = "recast")]
+  fn build_filtered_mesh_verts(
+    fixture: &RecastFixture,
+    filter: impl FnOnce(*mut rcContext, *mut rcHeightfield),
+  ) -> (i32, Vec<[u16; 3]>) {
+    let context = unsafe { CreateContext(false) };
+    let heightfield = fixture.rasterize(context);
+
+    filter(context, heightfield);
+
+    let compact_heightfield =
+      fixture.build_compact_heightfield(context, heightfield);
+
+    assert!(unsafe { rcBuildDistanceField(context, compact_heightfield) });
+    assert!(unsafe { rcBuildRegions(context, compact_heightfield, 0, 0, 0) });
+
+    let contour_set = build_contour_set(context, compact_heightfield);
+    let mesh = build_poly_mesh(context, contour_set);
+    let mesh_verts = unsafe {
+      std::slice::from_raw_parts((*mesh).verts, (*mesh).nverts as usize * 3)
+    }
+    .chunks(3)
+    .map(|vert| [vert[0], vert[1], vert[2]])
+    .collect();
+    let span_count = unsafe { (*compact_heightfield).spanCount };
+
+    unsafe { rcFreePolyMesh(mesh) };
+    unsafe { rcFreeContourSet(contour_set) };
+    unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    unsafe { DeleteContext(context) };
+    (span_count, mesh_verts)
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_filters_low_hanging_obstacles() {
+    // A step two voxels above the floor, which is unwalkable on its own (e.g.
+    // because of its steep sides).
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [12.0, 5.0, 12.0]);
+    fixture.add_quad([0.0, 0.0], [12.0, 12.0], 0.5, RC_WALKABLE_AREA);
+    fixture.add_quad([4.0, 4.0], [8.0, 8.0], 2.5, RC_NULL_AREA);
+
+    let on_step = |vert: &[u16; 3]| vert[1] == 3;
+
+    let (_, unfiltered_verts) = build_filtered_mesh_verts(&fixture, |_, _| {});
+    assert!(!unfiltered_verts.iter().any(on_step), "{unfiltered_verts:?}");
+
+    // The step is within climbing height of the floor below it, so it becomes
+    // walkable.
+    let (_, filtered_verts) =
+      build_filtered_mesh_verts(&fixture, |context, heightfield| unsafe {
+        rcFilterLowHangingWalkableObstacles(context, 2, heightfield)
+      });
+    assert!(filtered_verts.iter().any(on_step), "{filtered_verts:?}");
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_filters_ledge_spans() {
+    // A platform with a drop on every side.
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [12.0, 5.0, 12.0]);
+    fixture.add_quad([0.0, 0.0], [12.0, 12.0], 0.5, RC_WALKABLE_AREA);
+
+    let (unfiltered_span_count, unfiltered_verts) =
+      build_filtered_mesh_verts(&fixture, |_, _| {});
+    assert_eq!(unfiltered_span_count, 12 * 12);
+    assert!(unfiltered_verts.iter().any(|vert| vert[0] == 0));
+
+    // The outermost ring of spans is next to the drop, so it is removed.
+    let (filtered_span_count, filtered_verts) =
+      build_filtered_mesh_verts(&fixture, |context, heightfield| unsafe {
+        rcFilterLedgeSpans(context, 2, 1, heightfield)
+      });
+    assert_eq!(filtered_span_count, 10 * 10);
+    assert!(
+      filtered_verts
+        .iter()
+        .all(|vert| (1..=11).contains(&vert[0]) && (1..=11).contains(&vert[2])),
+      "{filtered_verts:?}"
+    );
+  }
+
+  #[cfg(feature = "recast")]
+  #[test]
+  fn recast_filters_walkable_low_height_spans() {
+    // A low ceiling spanning the floor from x=4 to x=8, leaving a single voxel
+    // of clearance below it.
+    let mut fixture = RecastFixture::new([0.0, 0.0, 0.0], [12.0, 5.0, 12.0]);
+    fixture.add_quad([0.0, 0.0], [12.0, 12.0], 0.5, RC_WALKABLE_AREA);
+    fixture.add_quad([4.0, 0.0], [8.0, 12.0], 2.5, RC_NULL_AREA);
+
+    let (unfiltered_span_count, _) =
+      build_filtered_mesh_verts(&fixture, |_, _| {});
+    assert_eq!(unfiltered_span_count, 12 * 12);
+
+    // The floor below the ceiling is too low for the agent, so the mesh is
+    // split in two.
+    let (filtered_span_count, filtered_verts) =
+      build_filtered_mesh_verts(&fixture, |context, heightfield| unsafe {
+        rcFilterWalkableLowHeightSpans(context, 2, heightfield)
+      });
+    assert_eq!(filtered_span_count, 8 * 12);
+    assert!(
+      !filtered_verts.iter().any(|vert| (5..8).contains(&vert[0])),
+      "{filtered_verts:?}"
+    );
+    assert!(filtered_verts.iter().any(|vert| vert[0] == 4));
+    assert!(filtered_verts.iter().any(|vert| vert[0] == 8));
+  }
+
+  #[cfg(all(feature = "recast", feature = "detour"))]
+  #[test]
+  fn recast_detail_mesh_creates_accurate_detour_heights() {
+    // A ramp rising from y=0 to y=1.875 over x=0..7.5, followed by a flat top.
+    // Contour tessellation splits the 12m edges every 3m, so the crease is kept
+    // off those vertices and no poly edge can run along it.
+    let (cs, ch) = (0.3f32, 0.1f32);
+    let (agent_height, agent_radius, agent_max_climb) =
+      (2.0f32, 0.6f32, 0.45f32);
+
+    let mut fixture = RecastFixture {
+      verts: vec![
+        0.0, 0.0, 0.0, //
+        7.5, 1.875, 0.0, //
+        7.5, 1.875, 12.0, //
+        0.0, 0.0, 12.0, //
+        12.0, 1.875, 0.0, //
+        12.0, 1.875, 12.0, //
+      ],
+      triangles: vec![0, 2, 1, 2, 0, 3, 1, 5, 4, 5, 1, 2],
+      cs,
+      ch,
+      walkable_height: (agent_height / ch).ceil() as i32,
+      walkable_climb: (agent_max_climb / ch).floor() as i32,
+      ..RecastFixture::new([0.0, 0.0, 0.0], [12.0, 3.0, 12.0])
+    };
+    let expected_height = |x: f32| (x * 0.25).min(1.875);
+
+    let context = unsafe { CreateContext(false) };
+
+    fixture.area_ids = vec![RC_NULL_AREA; fixture.triangles.len() / 3];
+    unsafe {
+      rcMarkWalkableTriangles(
+        context,
+        45.0,
+        fixture.verts.as_ptr(),
+        fixture.verts.len() as i32 / 3,
+        fixture.triangles.as_ptr(),
+        fixture.triangles.len() as i32 / 3,
+        fixture.area_ids.as_mut_ptr(),
+      )
+    };
+    assert_eq!(fixture.area_ids, [RC_WALKABLE_AREA; 4]);
+
+    let heightfield = fixture.rasterize(context);
+    let compact_heightfield =
+      fixture.build_compact_heightfield(context, heightfield);
+
+    assert!(unsafe { rcBuildDistanceField(context, compact_heightfield) });
+    assert!(unsafe { rcBuildRegions(context, compact_heightfield, 0, 8, 20) });
+
+    let contour_set = build_contour_set(context, compact_heightfield);
+    let mesh = build_poly_mesh(context, contour_set);
+
+    let detail_mesh = unsafe { rcAllocPolyMeshDetail() };
+    assert!(unsafe {
+      rcBuildPolyMeshDetail(
+        context,
+        mesh,
+        compact_heightfield,
+        /*sampleDist=*/ 6.0 * cs,
+        /*sampleMaxError=*/ ch,
+        detail_mesh,
+      )
+    });
+
+    let (mesh_ref, detail_mesh_ref) = unsafe { (&*mesh, &*detail_mesh) };
+    assert_eq!(detail_mesh_ref.nmeshes, mesh_ref.npolys);
+
+    // Recast leaves poly flags for the user to fill in.
+    unsafe {
+      std::slice::from_raw_parts_mut(mesh_ref.flags, mesh_ref.npolys as usize)
+    }
+    .fill(1);
+
+    let params = dtNavMeshCreateParams {
+      detailMeshes: detail_mesh_ref.meshes,
+      detailVerts: detail_mesh_ref.verts,
+      detailVertsCount: detail_mesh_ref.nverts,
+      detailTris: detail_mesh_ref.tris,
+      detailTriCount: detail_mesh_ref.ntris,
+      bmin: mesh_ref.bmin,
+      bmax: mesh_ref.bmax,
+      walkableHeight: agent_height,
+      walkableRadius: agent_radius,
+      walkableClimb: agent_max_climb,
+      cs: mesh_ref.cs,
+      ch: mesh_ref.ch,
+      buildBvTree: true,
+      ..create_nav_mesh_params(
+        unsafe {
+          std::slice::from_raw_parts(
+            mesh_ref.verts,
+            mesh_ref.nverts as usize * 3,
+          )
+        },
+        unsafe {
+          std::slice::from_raw_parts(
+            mesh_ref.polys,
+            (mesh_ref.npolys * mesh_ref.nvp * 2) as usize,
+          )
+        },
+        unsafe {
+          std::slice::from_raw_parts(mesh_ref.flags, mesh_ref.npolys as usize)
+        },
+        unsafe {
+          std::slice::from_raw_parts(mesh_ref.areas, mesh_ref.npolys as usize)
+        },
+        mesh_ref.nvp,
+      )
+    };
+    let (data, data_size) = create_nav_mesh_data(params);
+    let (no_detail_data, no_detail_data_size) =
+      create_nav_mesh_data(dtNavMeshCreateParams {
+        detailMeshes: std::ptr::null(),
+        detailVerts: std::ptr::null(),
+        detailVertsCount: 0,
+        detailTris: std::ptr::null(),
+        detailTriCount: 0,
+        ..params
+      });
+
+    let detail_tri_count = detail_mesh_ref.ntris;
+    let poly_count = mesh_ref.npolys;
+    unsafe { rcFreePolyMeshDetail(detail_mesh) };
+    unsafe { rcFreePolyMesh(mesh) };
+    unsafe { rcFreeContourSet(contour_set) };
+    unsafe { rcFreeCompactHeightfield(compact_heightfield) };
+    unsafe { DeleteContext(context) };
+
+    let nav_mesh = unsafe { &mut *create_nav_mesh(data, data_size) };
+
+    let header = unsafe { &*(*nav_mesh.getTileAt(0, 0, 0)).header };
+    assert_eq!(header.polyCount, poly_count);
+    assert_eq!(header.detailMeshCount, poly_count);
+    assert_eq!(header.detailTriCount, detail_tri_count);
+
+    let no_detail_nav_mesh =
+      create_nav_mesh(no_detail_data, no_detail_data_size);
+
+    let query = unsafe { &mut *dtAllocNavMeshQuery() };
+    assert_eq!(unsafe { query.init(nav_mesh, 512) }, DT_SUCCESS);
+    let no_detail_query = unsafe { &mut *dtAllocNavMeshQuery() };
+    assert_eq!(
+      unsafe { no_detail_query.init(no_detail_nav_mesh, 512) },
+      DT_SUCCESS
+    );
+
+    // Returns how far the height `query` finds at (x, z) is from the ramp.
+    // Recast raises the detail mesh one cell height above the voxels, so that
+    // is part of the expected height.
+    let height_error = |query: &dtNavMeshQuery, x: f32, z: f32| {
+      let pos = [x, expected_height(x), z];
+      let poly_ref = find_nearest_poly(query, &pos);
+      assert_ne!(poly_ref, 0);
+
+      let mut height = 0.0;
+      assert_eq!(
+        unsafe { query.getPolyHeight(poly_ref, pos.as_ptr(), &mut height) },
+        DT_SUCCESS
+      );
+      (height - (pos[1] + ch)).abs()
+    };
+    let tolerance = 1.5 * ch;
+
+    for x in [1.0, 3.0, 5.0, 7.0, 7.5, 8.0, 9.0, 11.0] {
+      let error = height_error(query, x, 6.0);
+      assert!(error < tolerance, "Height at x={x} is off by {error}");
+    }
+
+    // Without the detail mesh, heights are interpolated between the poly
+    // vertices, cutting the corner at the top of the ramp.
+    for z in [3.0, 6.0, 9.0] {
+      let error = height_error(no_detail_query, 7.5, z);
+      assert!(
+        error > tolerance,
+        "Height without detail at z={z} is only off by {error}"
+      );
+    }
+
+    unsafe { dtFreeNavMeshQuery(no_detail_query) };
+    unsafe { dtFreeNavMesh(no_detail_nav_mesh) };
+    unsafe { dtFreeNavMeshQuery(query) };
+    unsafe { dtFreeNavMesh(nav_mesh) };
+  }
 }